@@ -0,0 +1,52 @@
+#[macro_use]
+extern crate criterion;
+extern crate rurtp;
+
+use criterion::{black_box, Criterion};
+use rurtp::rtp::header::{Header, HeaderView};
+
+/// A 12 byte header with no CSRC identifiers or extension.
+fn minimal_header() -> Vec<u8> {
+	vec![0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1]
+}
+
+/// A header with the maximum of 15 CSRC identifiers.
+fn max_csrc_header() -> Vec<u8> {
+	let mut buf = vec![0b10001111, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+	for i in 0..15u8 {
+		buf.extend_from_slice(&[0, 0, 0, i]);
+	}
+	buf
+}
+
+/// A header with a 255 block extension.
+fn large_extension_header() -> Vec<u8> {
+	let mut buf = vec![0b10010000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xbe, 0xde, 0x00, 0xff];
+	for i in 0..255u8 {
+		buf.extend_from_slice(&[i, i, i, i]);
+	}
+	buf
+}
+
+fn bench_from_buf(c: &mut Criterion) {
+	let inputs = [
+		("minimal", minimal_header()),
+		("max_csrc", max_csrc_header()),
+		("large_extension", large_extension_header()),
+	];
+
+	let mut group = c.benchmark_group("from_buf");
+	for &(name, ref buf) in &inputs {
+		group.bench_function(name, |b| b.iter(|| Header::from_buf(black_box(buf))));
+	}
+	group.finish();
+
+	let mut group = c.benchmark_group("view_from_buf");
+	for &(name, ref buf) in &inputs {
+		group.bench_function(name, |b| b.iter(|| HeaderView::from_buf(black_box(buf))));
+	}
+	group.finish();
+}
+
+criterion_group!(benches, bench_from_buf);
+criterion_main!(benches);
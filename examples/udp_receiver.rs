@@ -0,0 +1,43 @@
+//! Receives RTP packets over UDP and prints a summary of each header.
+//!
+//! Run with an optional bind address (default `0.0.0.0:5004`):
+//!
+//! ```text
+//! cargo run --example udp_receiver -- 127.0.0.1:5004
+//! ```
+//!
+//! and send it a stream, for example with GStreamer:
+//!
+//! ```text
+//! gst-launch-1.0 audiotestsrc ! mulawenc ! rtppcmupay ! udpsink host=127.0.0.1 port=5004
+//! ```
+
+extern crate rurtp;
+
+use std::env;
+use std::net::UdpSocket;
+
+use rurtp::rtp::header::Header;
+
+fn main() {
+	let addr = env::args().nth(1).unwrap_or_else(|| "0.0.0.0:5004".to_string());
+	let socket = UdpSocket::bind(&addr).expect("failed to bind the UDP socket");
+	println!("listening on {}", addr);
+
+	// Large enough for any UDP datagram.
+	let mut buf = [0u8; 65535];
+	loop {
+		let (len, from) = match socket.recv_from(&mut buf) {
+			Ok(received) => received,
+			Err(e) => {
+				eprintln!("receive failed: {}", e);
+				continue;
+			}
+		};
+
+		match Header::from_buf(&buf[..len]) {
+			Ok(header) => println!("{} {} payload={}B", from, header, len - header.byte_len()),
+			Err(e) => eprintln!("{} dropped {} byte datagram: {}", from, len, e),
+		}
+	}
+}
@@ -0,0 +1,69 @@
+//! The demultiplexing module.
+//!
+//! This module distinguishes RTP from RTCP packets which share a single
+//! port per [RFC-5761](https://tools.ietf.org/html/rfc5761#section-4).
+
+use rtp::RtpError;
+
+/// The kind of packet in a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketKind {
+	/// An RTP packet.
+	Rtp,
+	/// An RTCP packet.
+	Rtcp,
+}
+
+/// Classifies a buffer as RTP or RTCP from its first two bytes, without
+/// parsing the rest of the packet.
+///
+/// The second byte is the RTCP packet type, or the RTP marker bit and
+/// payload type. RTCP packet types are in the range 192-223, which
+/// includes sender and receiver reports, SDES, BYE and APP (200-204),
+/// so the byte is RTCP if it falls in that range. This assumes, as
+/// RFC-5761 requires of multiplexed sessions, that RTP payload types
+/// 64-95 are not in use, since with the marker bit set they read as
+/// 192-223.
+///
+/// # Errors
+///
+/// If the buffer is shorter than two bytes or is not version 2 this
+/// method will return an Error.
+pub fn classify(buf: &[u8]) -> Result<PacketKind, RtpError> {
+	if buf.len() < 2 {
+		return Err(RtpError::BufferTooSmall { needed: 2, got: buf.len() });
+	}
+
+	let version = buf[0] >> 6;
+	if version != 2 {
+		return Err(RtpError::UnsupportedVersion(version));
+	}
+
+	match buf[1] {
+		192..=223 => Ok(PacketKind::Rtcp),
+		_ => Ok(PacketKind::Rtp),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn classify_packets() {
+		for pt in 200..=204 {
+			assert_eq!(classify(&[0x80, pt]), Ok(PacketKind::Rtcp));
+		}
+
+		// Payload type 96 with and without the marker bit.
+		assert_eq!(classify(&[0x80, 96]), Ok(PacketKind::Rtp));
+		assert_eq!(classify(&[0x80, 0x80 | 96]), Ok(PacketKind::Rtp));
+		assert_eq!(classify(&[0x80, 0]), Ok(PacketKind::Rtp));
+	}
+
+	#[test]
+	fn classify_errors() {
+		assert_eq!(classify(&[0x80]), Err(RtpError::BufferTooSmall { needed: 2, got: 1 }));
+		assert_eq!(classify(&[0x40, 200]), Err(RtpError::UnsupportedVersion(1)));
+	}
+}
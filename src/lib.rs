@@ -1,5 +1,22 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
 extern crate byteorder;
+#[cfg(feature = "bytes")]
+extern crate bytes;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "tokio-codec")]
+extern crate tokio_util;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
+pub mod demux;
+pub mod rtcp;
 pub mod rtp;
 
 #[cfg(test)]
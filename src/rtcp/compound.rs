@@ -0,0 +1,149 @@
+//! The RTCP compound packet module.
+//!
+//! RTCP packets are sent stacked together in compound packets, for
+//! example a sender report followed by a source description, per
+//! [RFC-3550](https://tools.ietf.org/html/rfc3550#section-6.1). Each packet
+//! gives its own length so the compound packet can be walked without
+//! understanding every packet type.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use rtp::RtpError;
+use super::{Goodbye, ReceiverReport, SenderReport};
+use super::{GOODBYE, RECEIVER_REPORT, SENDER_REPORT, SOURCE_DESCRIPTION};
+use super::sdes::SourceDescription;
+
+/// A packet within a compound RTCP packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RtcpPacket {
+	/// A sender report.
+	SenderReport(SenderReport),
+	/// A receiver report.
+	ReceiverReport(ReceiverReport),
+	/// A source description.
+	SourceDescription(SourceDescription),
+	/// A goodbye.
+	Goodbye(Goodbye),
+	/// A packet of another type, such as APP or feedback, which is skipped.
+	Other(u8),
+}
+
+/// An iterator over the packets in a compound RTCP packet.
+///
+/// If a packet's length runs past the buffer or the packet is malformed
+/// an error is yielded and iteration ends.
+#[derive(Debug, Clone)]
+pub struct CompoundRtcp<'a> {
+	buf: &'a [u8],
+}
+
+impl<'a> CompoundRtcp<'a> {
+	/// Constructs the iterator over a network buffer.
+	pub fn new(buf: &'a [u8]) -> Self {
+		CompoundRtcp { buf }
+	}
+
+	/// Splits the next packet from the buffer and parses it.
+	fn next_packet(&mut self) -> Result<RtcpPacket, RtpError> {
+		if self.buf.len() < 4 {
+			return Err(RtpError::BufferTooSmall { needed: 4, got: self.buf.len() });
+		}
+
+		// The length is the number of 32 bit words minus one.
+		let len = (NetworkEndian::read_u16(&self.buf[2..]) as usize + 1) * 4;
+		if self.buf.len() < len {
+			return Err(RtpError::BufferTooSmall { needed: len, got: self.buf.len() });
+		}
+
+		let (packet, rest) = self.buf.split_at(len);
+		self.buf = rest;
+
+		match packet[1] {
+			SENDER_REPORT => SenderReport::from_buf(packet).map(RtcpPacket::SenderReport),
+			RECEIVER_REPORT => ReceiverReport::from_buf(packet).map(RtcpPacket::ReceiverReport),
+			SOURCE_DESCRIPTION => SourceDescription::from_buf(packet).map(RtcpPacket::SourceDescription),
+			GOODBYE => Goodbye::from_buf(packet).map(RtcpPacket::Goodbye),
+			_ if packet[0] >> 6 != 2 => Err(RtpError::UnsupportedVersion(packet[0] >> 6)),
+			packet_type => Ok(RtcpPacket::Other(packet_type)),
+		}
+	}
+}
+
+impl<'a> Iterator for CompoundRtcp<'a> {
+	type Item = Result<RtcpPacket, RtpError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.buf.is_empty() {
+			return None;
+		}
+
+		let packet = self.next_packet();
+		if packet.is_err() {
+			// The rest of the compound packet cannot be trusted.
+			self.buf = &[];
+		}
+		Some(packet)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[cfg(not(feature = "std"))]
+	use alloc::vec::Vec;
+
+	#[test]
+	fn walks_compound_packet() {
+		let buf : &[u8] = &[
+			0x80, 201, 0x00, 0x01, // RR
+			0x00, 0x00, 0x00, 0x01,
+			0x81, 202, 0x00, 0x02, // SDES
+			0x00, 0x00, 0x00, 0x01,
+			0x01, 0x01, b'a', 0x00,
+			0x80, 206, 0x00, 0x00, // PSFB
+			0x81, 203, 0x00, 0x01, // BYE
+			0x00, 0x00, 0x00, 0x01,
+		];
+
+		let packets : Vec<_> = CompoundRtcp::new(buf).collect::<Result<_, _>>().unwrap();
+
+		assert_eq!(packets.len(), 4);
+		match packets[0] {
+			RtcpPacket::ReceiverReport(ref rr) => assert_eq!(rr.ssrc(), 1),
+			ref other => panic!("unexpected packet {:?}", other),
+		}
+		match packets[1] {
+			RtcpPacket::SourceDescription(ref sdes) => assert_eq!(sdes.source_count(), 1),
+			ref other => panic!("unexpected packet {:?}", other),
+		}
+		assert_eq!(packets[2], RtcpPacket::Other(206));
+		match packets[3] {
+			RtcpPacket::Goodbye(ref bye) => assert_eq!(bye.ssrcs(), &[1]),
+			ref other => panic!("unexpected packet {:?}", other),
+		}
+	}
+
+	#[test]
+	fn length_past_buffer() {
+		let buf : &[u8] = &[
+			0x80, 201, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x80, 201, 0x00, 0x05,
+			0x00, 0x00, 0x00, 0x01,
+		];
+
+		let mut iter = CompoundRtcp::new(buf);
+
+		assert!(iter.next().unwrap().is_ok());
+		assert_eq!(iter.next().unwrap(), Err(RtpError::BufferTooSmall { needed: 24, got: 8 }));
+		assert!(iter.next().is_none());
+	}
+
+	#[test]
+	fn truncated_common_header() {
+		let mut iter = CompoundRtcp::new(&[0x80, 201]);
+
+		assert!(iter.next().unwrap().is_err());
+		assert!(iter.next().is_none());
+	}
+}
@@ -0,0 +1,398 @@
+//! The RTCP module.
+//!
+//! This module provides parsers for RTCP control packets per [RFC-3550](https://tools.ietf.org/html/rfc3550).
+//! RTCP packets share the version and padding bit layout with RTP and are
+//! constructed from incoming network buffers.
+//!
+//! Every RTCP packet begins with the following common header:
+//!
+//!  0                   1                   2                   3
+//!  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |V=2|P|    RC   |      PT       |             length            |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+use byteorder::{ByteOrder, NetworkEndian};
+use rtp::RtpError;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+pub mod compound;
+pub mod sdes;
+pub mod stats;
+pub mod sync;
+
+/// The packet type of a sender report.
+pub const SENDER_REPORT: u8 = 200;
+
+/// The packet type of a receiver report.
+pub const RECEIVER_REPORT: u8 = 201;
+
+/// The packet type of a source description.
+pub const SOURCE_DESCRIPTION: u8 = 202;
+
+/// The packet type of a goodbye.
+pub const GOODBYE: u8 = 203;
+
+/// Parses the common RTCP header, checking the packet type matches and
+/// that the buffer contains the declared length.
+///
+/// Returns the report count and the body of the packet following the
+/// 4 byte common header.
+fn parse_common_header(buf: &[u8], packet_type: u8) -> Result<(u8, &[u8]), RtpError> {
+	if buf.len() < 4 {
+		return Err(RtpError::BufferTooSmall { needed: 4, got: buf.len() });
+	}
+
+	if buf[0] >> 6 != 2 {
+		return Err(RtpError::UnsupportedVersion(buf[0] >> 6));
+	}
+	if buf[1] != packet_type {
+		return Err(RtpError::RtcpError("Unexpected RTCP packet type."));
+	}
+
+	// The length is the number of 32 bit words minus one.
+	let len = (NetworkEndian::read_u16(&buf[2..]) as usize + 1) * 4;
+	if buf.len() < len {
+		return Err(RtpError::BufferTooSmall { needed: len, got: buf.len() });
+	}
+
+	Ok((buf[0] & 0b11111, &buf[4..len]))
+}
+
+/// Parses `count` report blocks from the buffer.
+fn parse_report_blocks(mut buf: &[u8], count: u8) -> Result<Vec<ReportBlock>, RtpError> {
+	if buf.len() < count as usize * 24 {
+		return Err(RtpError::RtcpError("Buffer does not contain the specified number of report blocks."));
+	}
+
+	let mut blocks = Vec::with_capacity(count as usize);
+	for _ in 0..count {
+		blocks.push(ReportBlock::from_buf(buf));
+		buf = &buf[24..];
+	}
+
+	Ok(blocks)
+}
+
+/// A reception report block.
+///
+/// These are carried by both sender and receiver reports and describe
+/// the reception statistics for a single source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportBlock {
+	ssrc: u32,
+	fraction_lost: u8,
+	cumulative_lost: i32,
+	highest_sequence: u32,
+	jitter: u32,
+	last_sender_report: u32,
+	delay_since_last_sender_report: u32,
+}
+
+impl ReportBlock {
+	/// Constructs the report block from the first 24 bytes of the buffer.
+	fn from_buf(buf: &[u8]) -> Self {
+		// The cumulative number lost is a signed 24 bit value.
+		let cumulative_lost = ((NetworkEndian::read_u32(&buf[4..]) << 8) as i32) >> 8;
+
+		ReportBlock {
+			ssrc: NetworkEndian::read_u32(buf),
+			fraction_lost: buf[4],
+			cumulative_lost,
+			highest_sequence: NetworkEndian::read_u32(&buf[8..]),
+			jitter: NetworkEndian::read_u32(&buf[12..]),
+			last_sender_report: NetworkEndian::read_u32(&buf[16..]),
+			delay_since_last_sender_report: NetworkEndian::read_u32(&buf[20..]),
+		}
+	}
+
+	/// Returns the SSRC of the source this block reports on.
+	pub fn ssrc(&self) -> u32 {
+		self.ssrc
+	}
+
+	/// Returns the fraction of packets lost since the last report.
+	pub fn fraction_lost(&self) -> u8 {
+		self.fraction_lost
+	}
+
+	/// Returns the cumulative number of packets lost.
+	pub fn cumulative_lost(&self) -> i32 {
+		self.cumulative_lost
+	}
+
+	/// Returns the extended highest sequence number received.
+	pub fn highest_sequence(&self) -> u32 {
+		self.highest_sequence
+	}
+
+	/// Returns the interarrival jitter.
+	pub fn jitter(&self) -> u32 {
+		self.jitter
+	}
+
+	/// Returns the middle 32 bits of the NTP timestamp of the last
+	/// sender report received.
+	pub fn last_sender_report(&self) -> u32 {
+		self.last_sender_report
+	}
+
+	/// Returns the delay since the last sender report was received in
+	/// units of 1/65536 seconds.
+	pub fn delay_since_last_sender_report(&self) -> u32 {
+		self.delay_since_last_sender_report
+	}
+}
+
+/// An RTCP sender report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenderReport {
+	ssrc: u32,
+	ntp_timestamp: u64,
+	rtp_timestamp: u32,
+	packet_count: u32,
+	octet_count: u32,
+	report_blocks: Vec<ReportBlock>,
+}
+
+impl SenderReport {
+	/// Construct the sender report from a network buffer.
+	///
+	/// # Errors
+	///
+	/// If the packet does not fit the format per [RFC-3550](https://tools.ietf.org/html/rfc3550)
+	/// this method will return an Error.
+	pub fn from_buf(buf: &[u8]) -> Result<Self, RtpError> {
+		let (count, body) = parse_common_header(buf, SENDER_REPORT)?;
+
+		if body.len() < 24 {
+			return Err(RtpError::RtcpError("Buffer is too small to contain a valid sender report."));
+		}
+
+		Ok(SenderReport {
+			ssrc: NetworkEndian::read_u32(body),
+			ntp_timestamp: NetworkEndian::read_u64(&body[4..]),
+			rtp_timestamp: NetworkEndian::read_u32(&body[12..]),
+			packet_count: NetworkEndian::read_u32(&body[16..]),
+			octet_count: NetworkEndian::read_u32(&body[20..]),
+			report_blocks: parse_report_blocks(&body[24..], count)?,
+		})
+	}
+
+	/// Returns the SSRC of the sender.
+	pub fn ssrc(&self) -> u32 {
+		self.ssrc
+	}
+
+	/// Returns the 64 bit NTP timestamp.
+	pub fn ntp_timestamp(&self) -> u64 {
+		self.ntp_timestamp
+	}
+
+	/// Returns the RTP timestamp corresponding to the NTP timestamp.
+	pub fn rtp_timestamp(&self) -> u32 {
+		self.rtp_timestamp
+	}
+
+	/// Returns the number of RTP packets sent.
+	pub fn packet_count(&self) -> u32 {
+		self.packet_count
+	}
+
+	/// Returns the number of payload octets sent.
+	pub fn octet_count(&self) -> u32 {
+		self.octet_count
+	}
+
+	/// Returns the reception report blocks.
+	pub fn report_blocks(&self) -> &[ReportBlock] {
+		&self.report_blocks
+	}
+}
+
+/// An RTCP receiver report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiverReport {
+	ssrc: u32,
+	report_blocks: Vec<ReportBlock>,
+}
+
+impl ReceiverReport {
+	/// Construct the receiver report from a network buffer.
+	///
+	/// # Errors
+	///
+	/// If the packet does not fit the format per [RFC-3550](https://tools.ietf.org/html/rfc3550)
+	/// this method will return an Error.
+	pub fn from_buf(buf: &[u8]) -> Result<Self, RtpError> {
+		let (count, body) = parse_common_header(buf, RECEIVER_REPORT)?;
+
+		if body.len() < 4 {
+			return Err(RtpError::RtcpError("Buffer is too small to contain a valid receiver report."));
+		}
+
+		Ok(ReceiverReport {
+			ssrc: NetworkEndian::read_u32(body),
+			report_blocks: parse_report_blocks(&body[4..], count)?,
+		})
+	}
+
+	/// Returns the SSRC of the packet sender.
+	pub fn ssrc(&self) -> u32 {
+		self.ssrc
+	}
+
+	/// Returns the reception report blocks.
+	pub fn report_blocks(&self) -> &[ReportBlock] {
+		&self.report_blocks
+	}
+}
+
+/// An RTCP goodbye (BYE) packet, sent when sources leave the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Goodbye {
+	ssrcs: Vec<u32>,
+	reason: Option<String>,
+}
+
+impl Goodbye {
+	/// Construct the goodbye from a network buffer.
+	///
+	/// # Errors
+	///
+	/// If the packet does not fit the format per [RFC-3550](https://tools.ietf.org/html/rfc3550)
+	/// or the reason is not valid UTF-8 this method will return an Error.
+	pub fn from_buf(buf: &[u8]) -> Result<Self, RtpError> {
+		let (count, body) = parse_common_header(buf, GOODBYE)?;
+
+		let ssrcs_len = count as usize * 4;
+		if body.len() < ssrcs_len {
+			return Err(RtpError::RtcpError("Buffer does not contain the specified number of SSRCs."));
+		}
+		let ssrcs = body[..ssrcs_len].chunks(4).map(NetworkEndian::read_u32).collect();
+
+		// The optional reason is a length byte followed by the text.
+		let rest = &body[ssrcs_len..];
+		let reason = match rest.first() {
+			Some(&len) if len > 0 => {
+				let text = rest.get(1..1 + len as usize)
+					.ok_or(RtpError::RtcpError("Goodbye reason exceeds the packet."))?;
+				let text = core::str::from_utf8(text)
+					.map_err(|_| RtpError::RtcpError("Goodbye reason is not valid UTF-8."))?;
+				Some(String::from(text))
+			}
+			_ => None,
+		};
+
+		Ok(Goodbye { ssrcs, reason })
+	}
+
+	/// Returns the SSRCs which are leaving.
+	pub fn ssrcs(&self) -> &[u32] {
+		&self.ssrcs
+	}
+
+	/// Returns the reason for leaving, if one was given.
+	pub fn reason(&self) -> Option<&str> {
+		self.reason.as_deref()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sender_report() {
+		let buf : &[u8] = &[
+			0x81, 200, 0x00, 0x0c, // V=2, RC=1, length=12
+			0x00, 0x00, 0x00, 0x01, // ssrc
+			0x00, 0x00, 0x00, 0x02, // ntp msw
+			0x80, 0x00, 0x00, 0x00, // ntp lsw
+			0x00, 0x00, 0x00, 0x03, // rtp timestamp
+			0x00, 0x00, 0x00, 0x04, // packet count
+			0x00, 0x00, 0x00, 0x05, // octet count
+			0x00, 0x00, 0x00, 0x06, // report block ssrc
+			0x10, 0xff, 0xff, 0xfe, // fraction lost, cumulative lost
+			0x00, 0x01, 0x00, 0x07, // highest sequence
+			0x00, 0x00, 0x00, 0x08, // jitter
+			0x00, 0x00, 0x00, 0x09, // lsr
+			0x00, 0x00, 0x00, 0x0a, // dlsr
+		];
+
+		let sr = SenderReport::from_buf(buf).unwrap();
+
+		assert_eq!(sr.ssrc(), 1);
+		assert_eq!(sr.ntp_timestamp(), 0x0000000280000000);
+		assert_eq!(sr.rtp_timestamp(), 3);
+		assert_eq!(sr.packet_count(), 4);
+		assert_eq!(sr.octet_count(), 5);
+		assert_eq!(sr.report_blocks().len(), 1);
+
+		let block = &sr.report_blocks()[0];
+		assert_eq!(block.ssrc(), 6);
+		assert_eq!(block.fraction_lost(), 0x10);
+		assert_eq!(block.cumulative_lost(), -2);
+		assert_eq!(block.highest_sequence(), 0x00010007);
+		assert_eq!(block.jitter(), 8);
+		assert_eq!(block.last_sender_report(), 9);
+		assert_eq!(block.delay_since_last_sender_report(), 10);
+	}
+
+	#[test]
+	fn receiver_report() {
+		let buf : &[u8] = &[0x80, 201, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
+
+		let rr = ReceiverReport::from_buf(buf).unwrap();
+
+		assert_eq!(rr.ssrc(), 1);
+		assert!(rr.report_blocks().is_empty());
+	}
+
+	#[test]
+	fn goodbye() {
+		let buf : &[u8] = &[
+			0x82, 203, 0x00, 0x04, // V=2, SC=2, length=4
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0x04, b'd', b'o', b'n',
+			b'e', 0x00, 0x00, 0x00,
+		];
+
+		let bye = Goodbye::from_buf(buf).unwrap();
+
+		assert_eq!(bye.ssrcs(), &[1, 2]);
+		assert_eq!(bye.reason(), Some("done"));
+
+		let buf : &[u8] = &[0x81, 203, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
+		assert_eq!(Goodbye::from_buf(buf).unwrap().reason(), None);
+
+		let buf : &[u8] = &[0x81, 203, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x04, b'd', 0x00, 0x00];
+		assert!(Goodbye::from_buf(buf).is_err());
+	}
+
+	#[test]
+	fn wrong_packet_type() {
+		let buf : &[u8] = &[0x80, 201, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
+
+		assert!(SenderReport::from_buf(buf).is_err());
+	}
+
+	#[test]
+	fn truncated_report_blocks() {
+		let buf : &[u8] = &[0x81, 201, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
+
+		assert!(ReceiverReport::from_buf(buf).is_err());
+	}
+
+	#[test]
+	fn truncated_length() {
+		let buf : &[u8] = &[0x80, 201, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01];
+
+		assert!(ReceiverReport::from_buf(buf).is_err());
+	}
+}
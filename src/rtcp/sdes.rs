@@ -0,0 +1,225 @@
+//! The RTCP source description module.
+//!
+//! Source description (SDES) packets carry items such as the canonical
+//! name of each source, per [RFC-3550](https://tools.ietf.org/html/rfc3550#section-6.5).
+
+use byteorder::{ByteOrder, NetworkEndian};
+use rtp::RtpError;
+use super::{parse_common_header, SOURCE_DESCRIPTION};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// The item type of the canonical name, which is stable across sessions
+/// and used to associate the SSRCs of a participant.
+pub const CNAME: u8 = 1;
+
+/// The item type of the user name.
+pub const NAME: u8 = 2;
+
+/// The item type of the email address.
+pub const EMAIL: u8 = 3;
+
+/// The item type of the phone number.
+pub const PHONE: u8 = 4;
+
+/// The item type of the geographic location.
+pub const LOC: u8 = 5;
+
+/// The item type of the application or tool name.
+pub const TOOL: u8 = 6;
+
+/// The item type of a notice about the source.
+pub const NOTE: u8 = 7;
+
+/// The item type of private extensions.
+pub const PRIV: u8 = 8;
+
+/// A source description item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdesItem {
+	item_type: u8,
+	value: Vec<u8>,
+}
+
+impl SdesItem {
+	/// Return the item type, such as `CNAME`.
+	pub fn item_type(&self) -> u8 {
+		self.item_type
+	}
+
+	/// Return the value as bytes.
+	pub fn value(&self) -> &[u8] {
+		&self.value
+	}
+
+	/// Return the value as text, or `None` if it is not valid UTF-8.
+	///
+	/// The values of the text items `CNAME` to `NOTE` are checked when
+	/// parsing and always succeed.
+	pub fn text(&self) -> Option<&str> {
+		core::str::from_utf8(&self.value).ok()
+	}
+}
+
+/// Parses the items of a chunk up to its END item, returning them and
+/// the remainder of the buffer after the chunk's padding.
+fn parse_items(buf: &[u8]) -> Result<(Vec<SdesItem>, &[u8]), RtpError> {
+	let mut items = Vec::new();
+	// The chunk began 4 bytes (the SSRC) before `buf`.
+	let mut offset = 0;
+
+	loop {
+		let item_type = *buf.get(offset).ok_or(RtpError::RtcpError("SDES chunk is missing its END item."))?;
+		offset += 1;
+
+		if item_type == 0 {
+			// The chunk is padded with nulls to a 32 bit boundary.
+			let end = (4 + offset).div_ceil(4) * 4 - 4;
+			let rest = buf.get(end..).ok_or(RtpError::RtcpError("SDES chunk padding exceeds the packet."))?;
+			return Ok((items, rest));
+		}
+
+		let len = *buf.get(offset).ok_or(RtpError::RtcpError("SDES item exceeds the packet."))? as usize;
+		offset += 1;
+		let value = buf.get(offset..offset + len).ok_or(RtpError::RtcpError("SDES item exceeds the packet."))?;
+		offset += len;
+
+		if item_type < PRIV && core::str::from_utf8(value).is_err() {
+			return Err(RtpError::RtcpError("SDES item is not valid UTF-8."));
+		}
+
+		items.push(SdesItem {
+			item_type,
+			value: value.to_vec(),
+		});
+	}
+}
+
+/// An RTCP source description packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDescription {
+	chunks: Vec<(u32, Vec<SdesItem>)>,
+}
+
+impl SourceDescription {
+	/// Construct the source description from a network buffer.
+	///
+	/// # Errors
+	///
+	/// If the packet does not fit the format per [RFC-3550](https://tools.ietf.org/html/rfc3550)
+	/// or a text item is not valid UTF-8 this method will return an Error.
+	pub fn from_buf(buf: &[u8]) -> Result<Self, RtpError> {
+		let (count, mut body) = parse_common_header(buf, SOURCE_DESCRIPTION)?;
+
+		let mut chunks = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			if body.len() < 4 {
+				return Err(RtpError::RtcpError("Buffer does not contain the specified number of SDES chunks."));
+			}
+
+			let ssrc = NetworkEndian::read_u32(body);
+			let (items, rest) = parse_items(&body[4..])?;
+			chunks.push((ssrc, items));
+			body = rest;
+		}
+
+		Ok(SourceDescription { chunks })
+	}
+
+	/// Returns the number of sources described.
+	pub fn source_count(&self) -> u8 {
+		self.chunks.len() as u8
+	}
+
+	/// Returns the SSRC and items of each chunk.
+	pub fn chunks(&self) -> &[(u32, Vec<SdesItem>)] {
+		&self.chunks
+	}
+
+	/// Returns the canonical name of the SSRC, if the packet carries one.
+	pub fn cname(&self, ssrc: u32) -> Option<&str> {
+		self.chunks.iter()
+			.filter(|&&(chunk_ssrc, _)| chunk_ssrc == ssrc)
+			.flat_map(|(_, items)| items.iter())
+			.find(|item| item.item_type == CNAME)
+			.and_then(|item| item.text())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_chunks() {
+		let buf : &[u8] = &[
+			0x82, 202, 0x00, 0x08, // V=2, SC=2, length=8
+			0x00, 0x00, 0x00, 0x01,
+			0x01, 0x05, b'a', b'l', // CNAME
+			b'i', b'c', b'e', 0x03, // EMAIL
+			0x03, b'a', b'@', b'b',
+			0x00, 0x00, 0x00, 0x00, // END and padding
+			0x00, 0x00, 0x00, 0x02,
+			0x02, 0x03, b'b', b'o', // NAME
+			b'b', 0x00, 0x00, 0x00, // END and padding
+		];
+
+		let sdes = SourceDescription::from_buf(buf).unwrap();
+
+		assert_eq!(sdes.source_count(), 2);
+		assert_eq!(sdes.cname(1), Some("alice"));
+		assert_eq!(sdes.cname(2), None);
+		assert_eq!(sdes.cname(3), None);
+
+		let (ssrc, ref items) = sdes.chunks()[0];
+		assert_eq!(ssrc, 1);
+		assert_eq!(items.len(), 2);
+		assert_eq!(items[1].item_type(), EMAIL);
+		assert_eq!(items[1].text(), Some("a@b"));
+
+		let (ssrc, ref items) = sdes.chunks()[1];
+		assert_eq!(ssrc, 2);
+		assert_eq!(items[0].item_type(), NAME);
+		assert_eq!(items[0].value(), b"bob");
+	}
+
+	#[test]
+	fn end_on_word_boundary_has_no_padding() {
+		// The END item falls at the end of a word, so the chunk is
+		// already aligned and the next chunk follows directly.
+		let buf : &[u8] = &[
+			0x82, 202, 0x00, 0x04,
+			0x00, 0x00, 0x00, 0x01,
+			0x01, 0x01, b'a', 0x00,
+			0x00, 0x00, 0x00, 0x02,
+			0x02, 0x01, b'b', 0x00,
+		];
+
+		let sdes = SourceDescription::from_buf(buf).unwrap();
+		assert_eq!(sdes.source_count(), 2);
+		assert_eq!(sdes.cname(1), Some("a"));
+		assert_eq!(sdes.chunks()[1].0, 2);
+		assert_eq!(sdes.chunks()[1].1[0].value(), b"b");
+	}
+
+	#[test]
+	fn invalid_items() {
+		let buf : &[u8] = &[
+			0x81, 202, 0x00, 0x02,
+			0x00, 0x00, 0x00, 0x01,
+			0x01, 0x02, 0xff, 0xfe,
+		];
+		assert_eq!(
+			SourceDescription::from_buf(buf),
+			Err(RtpError::RtcpError("SDES item is not valid UTF-8."))
+		);
+
+		let buf : &[u8] = &[
+			0x81, 202, 0x00, 0x02,
+			0x00, 0x00, 0x00, 0x01,
+			0x01, 0x07, b'a', b'b',
+		];
+		assert!(SourceDescription::from_buf(buf).is_err());
+	}
+}
@@ -0,0 +1,140 @@
+//! The RTCP statistics module.
+//!
+//! This module gathers the per-source counts an RTCP report describes from
+//! parsed RTP packets, per [RFC-3550](https://tools.ietf.org/html/rfc3550#section-6.4).
+
+use rtp::header::Header;
+use rtp::packet::Packet;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/// The packet and octet counts for a single SSRC.
+///
+/// As in a sender report the counts are 32 bits and wrap, and the octet
+/// count covers only the payload (excluding the header and padding).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceCounts {
+	packet_count: u32,
+	octet_count: u32,
+}
+
+impl SourceCounts {
+	/// Return the number of packets.
+	pub fn packet_count(&self) -> u32 {
+		self.packet_count
+	}
+
+	/// Return the number of payload octets.
+	pub fn octet_count(&self) -> u32 {
+		self.octet_count
+	}
+}
+
+/// The counts for each SSRC seen in a stream of RTP packets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RtcpStats {
+	sources: BTreeMap<u32, SourceCounts>,
+}
+
+impl RtcpStats {
+	/// Constructs empty statistics.
+	pub fn new() -> Self {
+		RtcpStats { sources: BTreeMap::new() }
+	}
+
+	/// Gathers the statistics from headers, each paired with the length
+	/// of its payload in bytes.
+	///
+	/// A `Header` does not know its payload length, so it is supplied
+	/// alongside for the octet count.
+	pub fn from_headers<'a, I>(headers: I) -> Self
+		where I: IntoIterator<Item = (&'a Header, usize)>
+	{
+		let mut stats = RtcpStats::new();
+		for (header, payload_len) in headers {
+			stats.observe(header, payload_len);
+		}
+		stats
+	}
+
+	/// Gathers the statistics from packets.
+	pub fn from_packets<'a, I>(packets: I) -> Self
+		where I: IntoIterator<Item = &'a Packet>
+	{
+		RtcpStats::from_headers(packets.into_iter().map(|p| (p.header(), p.payload().len())))
+	}
+
+	/// Counts a packet with the header and payload length.
+	pub fn observe(&mut self, header: &Header, payload_len: usize) {
+		let counts = self.sources.entry(header.ssrc_identifier()).or_default();
+		counts.packet_count = counts.packet_count.wrapping_add(1);
+		counts.octet_count = counts.octet_count.wrapping_add(payload_len as u32);
+	}
+
+	/// Returns an iterator over the distinct SSRCs, in ascending order.
+	pub fn ssrcs(&self) -> impl Iterator<Item = u32> + '_ {
+		self.sources.keys().cloned()
+	}
+
+	/// Returns the counts for the SSRC.
+	pub fn source(&self, ssrc: u32) -> Option<&SourceCounts> {
+		self.sources.get(&ssrc)
+	}
+
+	/// Returns the number of distinct SSRCs.
+	pub fn len(&self) -> usize {
+		self.sources.len()
+	}
+
+	/// Returns true if no packets were counted.
+	pub fn is_empty(&self) -> bool {
+		self.sources.is_empty()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[cfg(not(feature = "std"))]
+	use alloc::vec::Vec;
+
+	#[test]
+	fn interleaved_ssrcs() {
+		let a = Header::new(96, 1, 0, 0xaaaa);
+		let b = Header::new(96, 1, 0, 0xbbbb);
+
+		let stats = RtcpStats::from_headers(vec![(&a, 10), (&b, 20), (&a, 30), (&b, 5), (&a, 0)]);
+
+		assert_eq!(stats.ssrcs().collect::<Vec<_>>(), vec![0xaaaa, 0xbbbb]);
+		assert_eq!(stats.source(0xaaaa).unwrap().packet_count(), 3);
+		assert_eq!(stats.source(0xaaaa).unwrap().octet_count(), 40);
+		assert_eq!(stats.source(0xbbbb).unwrap().packet_count(), 2);
+		assert_eq!(stats.source(0xbbbb).unwrap().octet_count(), 25);
+		assert_eq!(stats.source(0xcccc), None);
+	}
+
+	#[test]
+	fn from_packets_counts_payload() {
+		let buf : &[u8] = &[
+			0b10100000, 96, 0x00, 0x01, // P
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x07,
+			0xaa, 0xbb, 0x00, 0x02,
+		];
+		let packet = Packet::from_buf(buf).unwrap();
+
+		let stats = RtcpStats::from_packets(vec![&packet, &packet]);
+
+		assert_eq!(stats.len(), 1);
+		assert_eq!(stats.source(7).unwrap().octet_count(), 4);
+	}
+
+	#[test]
+	fn empty() {
+		assert!(RtcpStats::from_headers(Vec::new()).is_empty());
+	}
+}
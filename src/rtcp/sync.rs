@@ -0,0 +1,151 @@
+//! The RTCP synchronization module.
+//!
+//! Sender reports pair an NTP wall-clock timestamp with the RTP timestamp
+//! of the same instant, per [RFC-3550](https://tools.ietf.org/html/rfc3550#section-6.4.1).
+//! This module extrapolates from that pair to place any RTP timestamp of
+//! the stream on the wall clock, which is the basis of lip-sync between
+//! streams.
+
+use super::SenderReport;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/// The most recent NTP to RTP timestamp pair of a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncPoint {
+	ntp_timestamp: u64,
+	rtp_timestamp: u32,
+	clock_rate: u32,
+}
+
+impl SyncPoint {
+	/// Returns the 64 bit NTP timestamp.
+	pub fn ntp_timestamp(&self) -> u64 {
+		self.ntp_timestamp
+	}
+
+	/// Returns the RTP timestamp corresponding to the NTP timestamp.
+	pub fn rtp_timestamp(&self) -> u32 {
+		self.rtp_timestamp
+	}
+
+	/// Returns the clock rate of the RTP timestamps.
+	pub fn clock_rate(&self) -> u32 {
+		self.clock_rate
+	}
+
+	/// Converts an RTP timestamp of the stream to an NTP timestamp.
+	///
+	/// RTP timestamps are 32 bits and wrap, so `rtp_timestamp` is taken
+	/// to be within 2^31 ticks of the sync point, before or after it.
+	pub fn to_ntp(&self, rtp_timestamp: u32) -> u64 {
+		let ticks = rtp_timestamp.wrapping_sub(self.rtp_timestamp) as i32 as i128;
+		// NTP timestamps are fixed point with 32 fractional bits.
+		let delta = (ticks << 32) / self.clock_rate as i128;
+
+		(self.ntp_timestamp as i128 + delta) as u64
+	}
+}
+
+/// Records the latest sync point of each SSRC from its sender reports.
+#[derive(Debug, Clone, Default)]
+pub struct SyncContext {
+	sources: BTreeMap<u32, SyncPoint>,
+}
+
+impl SyncContext {
+	/// Constructs a context without any sync points.
+	pub fn new() -> Self {
+		SyncContext { sources: BTreeMap::new() }
+	}
+
+	/// Records the sync point of the SSRC, replacing any earlier one.
+	///
+	/// # Panics
+	///
+	/// Panics if `clock_rate` is zero.
+	pub fn update(&mut self, ssrc: u32, ntp_timestamp: u64, rtp_timestamp: u32, clock_rate: u32) {
+		assert!(clock_rate > 0, "clock rate must be non-zero");
+
+		self.sources.insert(ssrc, SyncPoint { ntp_timestamp, rtp_timestamp, clock_rate });
+	}
+
+	/// Records the sync point carried by a sender report. The clock rate
+	/// is not carried by RTCP and comes from the payload format.
+	///
+	/// # Panics
+	///
+	/// Panics if `clock_rate` is zero.
+	pub fn update_from_report(&mut self, report: &SenderReport, clock_rate: u32) {
+		self.update(report.ssrc(), report.ntp_timestamp(), report.rtp_timestamp(), clock_rate);
+	}
+
+	/// Returns the sync point of the SSRC.
+	pub fn sync_point(&self, ssrc: u32) -> Option<&SyncPoint> {
+		self.sources.get(&ssrc)
+	}
+
+	/// Converts an RTP timestamp from the SSRC to an NTP timestamp, or
+	/// returns `None` if no sender report has been seen for the SSRC.
+	pub fn to_ntp(&self, ssrc: u32, rtp_timestamp: u32) -> Option<u64> {
+		self.sync_point(ssrc).map(|point| point.to_ntp(rtp_timestamp))
+	}
+
+	/// Forgets the SSRC, for example after an RTCP BYE.
+	pub fn remove(&mut self, ssrc: u32) {
+		self.sources.remove(&ssrc);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SECOND: u64 = 1 << 32;
+
+	#[test]
+	fn extrapolates_forwards_and_backwards() {
+		let mut sync = SyncContext::new();
+		sync.update(1, 100 * SECOND, 16000, 8000);
+
+		assert_eq!(sync.to_ntp(1, 16000), Some(100 * SECOND));
+		assert_eq!(sync.to_ntp(1, 24000), Some(101 * SECOND));
+		assert_eq!(sync.to_ntp(1, 12000), Some(99 * SECOND + SECOND / 2));
+		assert_eq!(sync.to_ntp(2, 16000), None);
+	}
+
+	#[test]
+	fn extrapolates_across_wrap() {
+		let mut sync = SyncContext::new();
+		sync.update(1, 100 * SECOND, u32::MAX - 3999, 8000);
+
+		assert_eq!(sync.to_ntp(1, 4000), Some(101 * SECOND));
+
+		sync.update(1, 100 * SECOND, 4000, 8000);
+		assert_eq!(sync.to_ntp(1, u32::MAX - 3999), Some(99 * SECOND));
+	}
+
+	#[test]
+	fn from_sender_report() {
+		let buf : &[u8] = &[
+			0x80, 200, 0x00, 0x06,
+			0x00, 0x00, 0x00, 0x07, // ssrc
+			0x00, 0x00, 0x00, 0x02, // ntp msw
+			0x00, 0x00, 0x00, 0x00, // ntp lsw
+			0x00, 0x00, 0x03, 0xe8, // rtp timestamp
+			0x00, 0x00, 0x00, 0x00,
+			0x00, 0x00, 0x00, 0x00,
+		];
+		let report = SenderReport::from_buf(buf).unwrap();
+
+		let mut sync = SyncContext::new();
+		sync.update_from_report(&report, 1000);
+
+		assert_eq!(sync.to_ntp(7, 2000), Some(3 * SECOND));
+		sync.remove(7);
+		assert!(sync.sync_point(7).is_none());
+	}
+}
@@ -0,0 +1,95 @@
+//! The RTP codec module.
+//!
+//! This module provides a `tokio_util` decoder for RTP packets carried over
+//! a stream transport with the two byte length prefix framing of
+//! [RFC-4571](https://tools.ietf.org/html/rfc4571).
+
+use super::packet::Packet;
+use bytes::{Buf, BytesMut};
+use std::io;
+use tokio_util::codec::Decoder;
+
+/// A decoder for RFC-4571 framed RTP packets.
+///
+/// Incomplete frames are left in the buffer until the rest of the frame
+/// arrives. A frame which does not contain a valid packet is consumed and
+/// reported as an `io::ErrorKind::InvalidData` error wrapping the
+/// `RtpError`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RtpDecoder;
+
+impl RtpDecoder {
+	/// Constructs the decoder.
+	pub fn new() -> Self {
+		RtpDecoder
+	}
+}
+
+impl Decoder for RtpDecoder {
+	type Item = Packet;
+	type Error = io::Error;
+
+	fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Packet>, io::Error> {
+		if src.len() < 2 {
+			return Ok(None);
+		}
+
+		let frame_len = 2 + ((src[0] as usize) << 8 | src[1] as usize);
+		if src.len() < frame_len {
+			src.reserve(frame_len - src.len());
+			return Ok(None);
+		}
+
+		let mut frame = src.split_to(frame_len);
+		frame.advance(2);
+
+		Packet::from_buf(&frame)
+			.map(Some)
+			.map_err(io::Error::from)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_across_partial_reads() {
+		let stream : &[u8] = &[
+			0x00, 0x0d,
+			0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa,
+			0x00, 0x0c,
+			0b10000000, 96, 0, 2, 0, 0, 0, 1, 0, 0, 0, 1,
+		];
+
+		let mut decoder = RtpDecoder::new();
+		let mut buf = BytesMut::new();
+
+		buf.extend_from_slice(&stream[..1]);
+		assert!(decoder.decode(&mut buf).unwrap().is_none());
+
+		buf.extend_from_slice(&stream[1..10]);
+		assert!(decoder.decode(&mut buf).unwrap().is_none());
+
+		buf.extend_from_slice(&stream[10..20]);
+		let packet = decoder.decode(&mut buf).unwrap().unwrap();
+		assert_eq!(packet.header().sequence(), 1);
+		assert_eq!(packet.payload(), &[0xaa]);
+		assert!(decoder.decode(&mut buf).unwrap().is_none());
+
+		buf.extend_from_slice(&stream[20..]);
+		let packet = decoder.decode(&mut buf).unwrap().unwrap();
+		assert_eq!(packet.header().sequence(), 2);
+		assert!(buf.is_empty());
+	}
+
+	#[test]
+	fn invalid_frame_is_consumed() {
+		let mut buf = BytesMut::from(&[0x00, 0x02, 0xff, 0xff, 0x00][..]);
+		let mut decoder = RtpDecoder::new();
+
+		let err = decoder.decode(&mut buf).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+		assert_eq!(&buf[..], &[0x00]);
+	}
+}
@@ -0,0 +1,234 @@
+//! The RTP header extension element module.
+//!
+//! This module provides parsing of the structured header extension elements
+//! defined in [RFC-5285](https://tools.ietf.org/html/rfc5285).
+//!
+//! The one-byte header profile (0xBEDE) packs each element as:
+//!
+//!  0
+//!  0 1 2 3 4 5 6 7
+//! +-+-+-+-+-+-+-+-+
+//! |  ID   |  len  |  followed by len + 1 bytes of data
+//! +-+-+-+-+-+-+-+-+
+//!
+//! The two-byte header profile (0x100X) packs each element as:
+//!
+//!  0                   1
+//!  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |       ID      |     length    |  followed by length bytes of data
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+use super::RtpError;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// The extension id of the one-byte header profile.
+pub const ONE_BYTE_PROFILE: u16 = 0xBEDE;
+
+/// The extension id of the two-byte header profile, ignoring the
+/// 4 application bits.
+pub const TWO_BYTE_PROFILE: u16 = 0x1000;
+
+/// The profile of a header extension, given by its extension id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExtensionProfile {
+	/// The RFC-5285 one-byte header profile (0xBEDE).
+	OneByte,
+	/// The RFC-5285 two-byte header profile (0x1000-0x100F).
+	TwoByte,
+	/// Any other profile specific extension.
+	Other(u16),
+}
+
+impl ExtensionProfile {
+	/// Classifies an extension id.
+	pub fn from_id(extension_id: u16) -> Self {
+		if extension_id == ONE_BYTE_PROFILE {
+			ExtensionProfile::OneByte
+		} else if extension_id & 0xFFF0 == TWO_BYTE_PROFILE {
+			ExtensionProfile::TwoByte
+		} else {
+			ExtensionProfile::Other(extension_id)
+		}
+	}
+}
+
+/// A single element of an RFC-5285 header extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionElement {
+	id: u8,
+	data: Vec<u8>,
+}
+
+impl ExtensionElement {
+	/// Constructs an element from its local identifier and data.
+	pub fn new(id: u8, data: Vec<u8>) -> Self {
+		ExtensionElement { id, data }
+	}
+
+	/// Return the local identifier of the element.
+	pub fn id(&self) -> u8 {
+		self.id
+	}
+
+	/// Return the data of the element.
+	pub fn data(&self) -> &[u8] {
+		&self.data
+	}
+}
+
+/// The contents of a header extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtensionData<'a> {
+	/// The elements of a one-byte or two-byte profile extension.
+	Elements(Vec<ExtensionElement>),
+	/// The 32bit chunks of any other profile specific extension, which
+	/// are left to the profile to interpret.
+	Raw(&'a [u32]),
+}
+
+/// Parses the extension data into elements according to the profile
+/// given by the extension id.
+pub fn parse_elements(extension_id: u16, data: &[u8]) -> Result<Vec<ExtensionElement>, RtpError> {
+	match ExtensionProfile::from_id(extension_id) {
+		ExtensionProfile::OneByte => parse_one_byte(data),
+		ExtensionProfile::TwoByte => parse_two_byte(data),
+		ExtensionProfile::Other(_) => Err(RtpError::HeaderError("Header extension profile is not RFC-5285.")),
+	}
+}
+
+fn parse_one_byte(mut data: &[u8]) -> Result<Vec<ExtensionElement>, RtpError> {
+	let mut elements = Vec::new();
+
+	while !data.is_empty() {
+		let id = data[0] >> 4;
+		let len = (data[0] & 0b1111) as usize + 1;
+		data = &data[1..];
+
+		// Zero bytes are padding between elements.
+		if id == 0 {
+			continue;
+		}
+		// The id 15 is reserved and terminates processing.
+		if id == 15 {
+			break;
+		}
+
+		if data.len() < len {
+			return Err(RtpError::HeaderError("Header extension element exceeds extension data."));
+		}
+
+		elements.push(ExtensionElement { id, data: data[..len].to_vec() });
+		data = &data[len..];
+	}
+
+	Ok(elements)
+}
+
+fn parse_two_byte(mut data: &[u8]) -> Result<Vec<ExtensionElement>, RtpError> {
+	let mut elements = Vec::new();
+
+	while !data.is_empty() {
+		let id = data[0];
+		data = &data[1..];
+
+		// Zero bytes are padding between elements.
+		if id == 0 {
+			continue;
+		}
+
+		if data.is_empty() {
+			return Err(RtpError::HeaderError("Header extension element is missing its length."));
+		}
+		let len = data[0] as usize;
+		data = &data[1..];
+
+		if data.len() < len {
+			return Err(RtpError::HeaderError("Header extension element exceeds extension data."));
+		}
+
+		elements.push(ExtensionElement { id, data: data[..len].to_vec() });
+		data = &data[len..];
+	}
+
+	Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn profiles() {
+		assert_eq!(ExtensionProfile::from_id(0xBEDE), ExtensionProfile::OneByte);
+		assert_eq!(ExtensionProfile::from_id(0x1000), ExtensionProfile::TwoByte);
+		assert_eq!(ExtensionProfile::from_id(0x100F), ExtensionProfile::TwoByte);
+		assert_eq!(ExtensionProfile::from_id(0x1010), ExtensionProfile::Other(0x1010));
+		assert_eq!(ExtensionProfile::from_id(0), ExtensionProfile::Other(0));
+	}
+
+	#[test]
+	fn one_byte_elements() {
+		let data = &[
+			0x10, 0xaa, // id 1, 1 byte
+			0x00, // padding
+			0x21, 0xbb, 0xcc, // id 2, 2 bytes
+			0x00, 0x00,
+		];
+
+		let elements = parse_elements(0xBEDE, data).unwrap();
+
+		assert_eq!(elements.len(), 2);
+		assert_eq!(elements[0].id(), 1);
+		assert_eq!(elements[0].data(), &[0xaa]);
+		assert_eq!(elements[1].id(), 2);
+		assert_eq!(elements[1].data(), &[0xbb, 0xcc]);
+	}
+
+	#[test]
+	fn one_byte_reserved_id_terminates() {
+		let data = &[0x10, 0xaa, 0xf0, 0x20, 0xbb];
+
+		let elements = parse_elements(0xBEDE, data).unwrap();
+
+		assert_eq!(elements.len(), 1);
+	}
+
+	#[test]
+	fn one_byte_truncated_element() {
+		let data = &[0x13, 0xaa, 0xbb];
+
+		assert!(parse_elements(0xBEDE, data).is_err());
+	}
+
+	#[test]
+	fn two_byte_elements() {
+		let data = &[
+			0x01, 0x00, // id 1, empty
+			0x00, // padding
+			0x02, 0x03, 0xaa, 0xbb, 0xcc, // id 2, 3 bytes
+		];
+
+		let elements = parse_elements(0x1005, data).unwrap();
+
+		assert_eq!(elements.len(), 2);
+		assert_eq!(elements[0].id(), 1);
+		assert!(elements[0].data().is_empty());
+		assert_eq!(elements[1].id(), 2);
+		assert_eq!(elements[1].data(), &[0xaa, 0xbb, 0xcc]);
+	}
+
+	#[test]
+	fn two_byte_truncated_element() {
+		let data = &[0x01, 0x04, 0xaa];
+
+		assert!(parse_elements(0x1000, data).is_err());
+	}
+
+	#[test]
+	fn unknown_profile() {
+		assert!(parse_elements(0x1234, &[0x10, 0xaa]).is_err());
+	}
+}
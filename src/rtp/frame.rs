@@ -0,0 +1,676 @@
+//! The RTP frame module.
+//!
+//! Many payload formats split a single media frame across several packets
+//! which share a timestamp, with the marker bit set on the last packet of
+//! the frame. This module detects those frame boundaries from the headers,
+//! splits a frame's payload into packets for sending, and reassembles the
+//! packets into frames on receipt.
+
+use core::cmp::Ordering;
+use super::header::Header;
+use super::packet::Packet;
+use super::sequence::seq_cmp;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+/// A completed frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+	ssrc: u32,
+	timestamp: u32,
+	first_sequence: u16,
+	last_sequence: u16,
+	packet_count: usize,
+	marker: bool,
+}
+
+impl Frame {
+	/// Starts a frame from its first header.
+	fn start(header: &Header) -> Self {
+		Frame {
+			ssrc: header.ssrc_identifier(),
+			timestamp: header.timestamp(),
+			first_sequence: header.sequence(),
+			last_sequence: header.sequence(),
+			packet_count: 1,
+			marker: header.has_marker(),
+		}
+	}
+
+	/// Returns true if the header belongs to this frame.
+	fn contains(&self, header: &Header) -> bool {
+		self.ssrc == header.ssrc_identifier() && self.timestamp == header.timestamp()
+	}
+
+	/// Adds a header to the frame, widening the sequence range.
+	fn add(&mut self, header: &Header) {
+		let seq = header.sequence();
+		if seq_cmp(seq, self.first_sequence) == Ordering::Less {
+			self.first_sequence = seq;
+		}
+		if seq_cmp(seq, self.last_sequence) == Ordering::Greater {
+			self.last_sequence = seq;
+		}
+		self.packet_count += 1;
+		self.marker |= header.has_marker();
+	}
+
+	/// Returns the SSRC of the frame.
+	pub fn ssrc(&self) -> u32 {
+		self.ssrc
+	}
+
+	/// Returns the timestamp shared by the packets of the frame.
+	pub fn timestamp(&self) -> u32 {
+		self.timestamp
+	}
+
+	/// Returns the first and last sequence numbers of the frame (inclusive).
+	pub fn sequence_range(&self) -> (u16, u16) {
+		(self.first_sequence, self.last_sequence)
+	}
+
+	/// Returns the number of packets seen for the frame.
+	pub fn packet_count(&self) -> usize {
+		self.packet_count
+	}
+
+	/// Returns true if the frame was completed by a packet with the marker
+	/// bit set, rather than by a change of SSRC or timestamp.
+	pub fn has_marker(&self) -> bool {
+		self.marker
+	}
+}
+
+/// Detects frame boundaries from headers in arrival order.
+///
+/// A frame is completed when a header with the marker bit is seen, or when
+/// a header arrives with a different SSRC or timestamp to the current frame.
+#[derive(Debug, Default)]
+pub struct FrameAssembler {
+	current: Option<Frame>,
+	completed: VecDeque<Frame>,
+}
+
+impl FrameAssembler {
+	/// Constructs an empty frame assembler.
+	pub fn new() -> Self {
+		FrameAssembler::default()
+	}
+
+	/// Pushes the next header, completing any frames it ends.
+	pub fn push(&mut self, header: &Header) {
+		let mut frame = match self.current.take() {
+			Some(mut frame) => {
+				if frame.contains(header) {
+					frame.add(header);
+					frame
+				} else {
+					self.completed.push_back(frame);
+					Frame::start(header)
+				}
+			}
+			None => Frame::start(header),
+		};
+
+		if header.has_marker() {
+			frame.marker = true;
+			self.completed.push_back(frame);
+		} else {
+			self.current = Some(frame);
+		}
+	}
+
+	/// Returns the next completed frame.
+	pub fn next_frame(&mut self) -> Option<Frame> {
+		self.completed.pop_front()
+	}
+
+	/// Completes the current frame, for example at the end of a stream.
+	pub fn flush(&mut self) -> Option<Frame> {
+		self.current.take()
+	}
+}
+
+/// Splits a payload into packets of at most `max_payload` bytes.
+///
+/// Each packet is a copy of `header` with the sequence number incremented
+/// from the header's own, the same timestamp, and the marker bit set only
+/// on the final packet. The packets carry no padding, so the padding flag
+/// is cleared. An empty payload gives a single empty packet.
+///
+/// # Panics
+///
+/// Panics if `max_payload` is zero.
+pub fn fragment(header: &Header, payload: &[u8], max_payload: usize) -> Vec<Packet> {
+	assert!(max_payload > 0, "max_payload must be non-zero");
+
+	let count = payload.len().div_ceil(max_payload).max(1);
+	let mut packets = Vec::with_capacity(count);
+
+	for i in 0..count {
+		let start = i * max_payload;
+		let end = payload.len().min(start + max_payload);
+
+		let mut fragment_header = header.clone();
+		fragment_header.set_sequence(header.sequence().wrapping_add(i as u16));
+		fragment_header.set_marker(i == count - 1);
+
+		packets.push(Packet::new(fragment_header, payload[start..end].to_vec()));
+	}
+
+	packets
+}
+
+/// A frame reassembled from its packets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReassembledFrame {
+	timestamp: u32,
+	first_sequence: u16,
+	last_sequence: u16,
+	payload: Vec<u8>,
+}
+
+impl ReassembledFrame {
+	/// Returns the timestamp of the frame.
+	pub fn timestamp(&self) -> u32 {
+		self.timestamp
+	}
+
+	/// Returns the first and last sequence numbers of the frame (inclusive).
+	pub fn sequence_range(&self) -> (u16, u16) {
+		(self.first_sequence, self.last_sequence)
+	}
+
+	/// Returns the payloads of the packets concatenated in sequence order.
+	pub fn payload(&self) -> &[u8] {
+		&self.payload
+	}
+}
+
+/// A frame which was evicted before all of its packets arrived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompleteFrame {
+	timestamp: u32,
+	received: usize,
+	missing: Vec<u16>,
+	has_marker: bool,
+}
+
+impl IncompleteFrame {
+	/// Returns the timestamp of the frame.
+	pub fn timestamp(&self) -> u32 {
+		self.timestamp
+	}
+
+	/// Returns the number of packets received for the frame.
+	pub fn received(&self) -> usize {
+		self.received
+	}
+
+	/// Returns the sequence numbers missing between the first packet
+	/// received and the last.
+	///
+	/// Packets missing from either end of the frame cannot be known.
+	pub fn missing(&self) -> &[u16] {
+		&self.missing
+	}
+
+	/// Returns true if the final packet of the frame was received.
+	pub fn has_marker(&self) -> bool {
+		self.has_marker
+	}
+}
+
+/// A frame awaiting the rest of its packets.
+#[derive(Debug)]
+struct PendingFrame {
+	timestamp: u32,
+	/// The received packets ordered by sequence number.
+	packets: Vec<(u16, Vec<u8>)>,
+	marker: Option<u16>,
+}
+
+impl PendingFrame {
+	/// Inserts a packet in sequence order, ignoring duplicates.
+	fn insert(&mut self, seq: u16, payload: &[u8]) {
+		let pos = self.packets.iter().position(|&(s, _)| seq_cmp(s, seq) != Ordering::Less);
+		match pos {
+			Some(i) if self.packets[i].0 == seq => {}
+			Some(i) => self.packets.insert(i, (seq, payload.to_vec())),
+			None => self.packets.push((seq, payload.to_vec())),
+		}
+	}
+
+	/// Returns the sequence numbers missing between the first and last
+	/// packets received.
+	fn missing(&self) -> Vec<u16> {
+		let mut missing = Vec::new();
+		for pair in self.packets.windows(2) {
+			let mut seq = pair[0].0.wrapping_add(1);
+			while seq != pair[1].0 {
+				missing.push(seq);
+				seq = seq.wrapping_add(1);
+			}
+		}
+		missing
+	}
+
+	/// Returns true if the final packet has arrived and no packets are
+	/// missing before it.
+	fn is_contiguous_to_marker(&self) -> bool {
+		match self.marker {
+			// The packets are ordered without duplicates, so they are
+			// contiguous when they span exactly as many sequence numbers.
+			Some(last) => self.packets.last().map(|p| p.0) == Some(last) &&
+				last.wrapping_sub(self.first_sequence()) as usize + 1 == self.packets.len(),
+			None => false,
+		}
+	}
+
+	/// Returns the first sequence number received.
+	fn first_sequence(&self) -> u16 {
+		self.packets[0].0
+	}
+}
+
+/// The number of recent frame ends remembered to find where frames start.
+const MAX_FRAME_ENDS: usize = 16;
+
+/// The number of frames which may await packets at once.
+const MAX_PENDING_FRAMES: usize = 64;
+
+/// Reassembles frames from packets which may arrive out of order.
+///
+/// Packets are grouped by timestamp, so a reassembler should only be given
+/// the packets of a single SSRC. A frame is complete once the packet with
+/// the marker bit has arrived, the sequence numbers up to it are contiguous
+/// and the packet before the first is known to end the previous frame.
+/// Until a frame end has been seen (at the start of a stream) the first
+/// packet received for a frame is assumed to be its first.
+///
+/// Frames which never complete stay pending until they are evicted with
+/// `evict_older_than`. At most 64 frames are kept pending, and a packet
+/// for a new frame beyond that drops the frame with the oldest timestamp.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+	pending: Vec<PendingFrame>,
+	/// The sequence numbers of recent packets with the marker bit.
+	ends: VecDeque<u16>,
+	completed: VecDeque<ReassembledFrame>,
+}
+
+impl Reassembler {
+	/// Constructs an empty reassembler.
+	pub fn new() -> Self {
+		Reassembler::default()
+	}
+
+	/// Adds a packet, completing any frames which now have all of their
+	/// packets.
+	pub fn push(&mut self, packet: &Packet) {
+		let header = packet.header();
+		let timestamp = header.timestamp();
+
+		let seq = header.sequence();
+
+		let index = match self.pending.iter().position(|f| f.timestamp == timestamp) {
+			Some(index) => index,
+			None => {
+				if self.pending.len() == MAX_PENDING_FRAMES {
+					self.evict_oldest(timestamp);
+				}
+				self.pending.push(PendingFrame { timestamp, packets: Vec::new(), marker: None });
+				self.pending.len() - 1
+			}
+		};
+
+		let mut ended = false;
+		{
+			let frame = &mut self.pending[index];
+			frame.insert(seq, packet.payload());
+			if header.has_marker() && frame.marker.is_none() {
+				frame.marker = Some(seq);
+				if self.ends.len() == MAX_FRAME_ENDS {
+					self.ends.pop_front();
+				}
+				self.ends.push_back(seq);
+				ended = true;
+			}
+		}
+
+		let mut end = self.complete(index);
+		if ended && end.is_none() {
+			end = Some(seq);
+		}
+
+		// The end of a frame may complete the following frame if it
+		// already has all of its packets, and so on along the chain.
+		while let Some(last) = end {
+			end = self.pending.iter()
+				.position(|f| f.first_sequence() == last.wrapping_add(1))
+				.and_then(|next| self.complete(next));
+		}
+	}
+
+	/// Moves the pending frame at `index` to the completed frames if it
+	/// has all of its packets, returning its last sequence number.
+	fn complete(&mut self, index: usize) -> Option<u16> {
+		if !self.is_complete(&self.pending[index]) {
+			return None;
+		}
+
+		let frame = self.pending.remove(index);
+		let first_sequence = frame.first_sequence();
+		let last_sequence = frame.packets[frame.packets.len() - 1].0;
+
+		self.completed.push_back(ReassembledFrame {
+			timestamp: frame.timestamp,
+			first_sequence,
+			last_sequence,
+			payload: frame.packets.into_iter().flat_map(|(_, payload)| payload).collect(),
+		});
+		Some(last_sequence)
+	}
+
+	/// Drops the pending frame with the oldest timestamp relative to
+	/// `timestamp`, using 32 bit serial number arithmetic.
+	fn evict_oldest(&mut self, timestamp: u32) {
+		let oldest = self.pending.iter()
+			.enumerate()
+			.min_by_key(|&(_, f)| f.timestamp.wrapping_sub(timestamp) as i32)
+			.map(|(i, _)| i);
+
+		if let Some(index) = oldest {
+			self.pending.remove(index);
+		}
+	}
+
+	/// Returns the next completed frame.
+	pub fn next_frame(&mut self) -> Option<ReassembledFrame> {
+		self.completed.pop_front()
+	}
+
+	/// Returns true if the frame has all of its packets.
+	fn is_complete(&self, frame: &PendingFrame) -> bool {
+		if !frame.is_contiguous_to_marker() {
+			return false;
+		}
+
+		let first = frame.first_sequence();
+		let previous = first.wrapping_sub(1);
+
+		// Either the previous frame ended just before this one, or no
+		// earlier frame end is known.
+		self.ends.contains(&previous) ||
+			!self.ends.iter().any(|&end| seq_cmp(end, first) == Ordering::Less)
+	}
+
+	/// Returns the number of frames awaiting packets.
+	pub fn pending(&self) -> usize {
+		self.pending.len()
+	}
+
+	/// Removes and reports the pending frames with a timestamp older than
+	/// `timestamp`, using 32 bit serial number arithmetic.
+	pub fn evict_older_than(&mut self, timestamp: u32) -> Vec<IncompleteFrame> {
+		let mut evicted = Vec::new();
+		let mut i = 0;
+
+		while i < self.pending.len() {
+			if (self.pending[i].timestamp.wrapping_sub(timestamp) as i32) < 0 {
+				let frame = self.pending.remove(i);
+				evicted.push(IncompleteFrame {
+					timestamp: frame.timestamp,
+					received: frame.packets.len(),
+					missing: frame.missing(),
+					has_marker: frame.marker.is_some(),
+				});
+			} else {
+				i += 1;
+			}
+		}
+
+		evicted
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rtp::header::HeaderBuilder;
+
+	fn header(seq: u16, ts: u32, ssrc: u32, marker: bool) -> Header {
+		HeaderBuilder::new()
+			.sequence(seq)
+			.timestamp(ts)
+			.ssrc(ssrc)
+			.marker(marker)
+			.build()
+			.unwrap()
+	}
+
+	#[test]
+	fn marker_completes_frame() {
+		let mut assembler = FrameAssembler::new();
+
+		assembler.push(&header(1, 100, 1, false));
+		assembler.push(&header(2, 100, 1, false));
+		assert!(assembler.next_frame().is_none());
+
+		assembler.push(&header(3, 100, 1, true));
+		let frame = assembler.next_frame().unwrap();
+
+		assert_eq!(frame.sequence_range(), (1, 3));
+		assert_eq!(frame.packet_count(), 3);
+		assert_eq!(frame.timestamp(), 100);
+		assert!(frame.has_marker());
+		assert!(assembler.next_frame().is_none());
+		assert!(assembler.flush().is_none());
+	}
+
+	#[test]
+	fn timestamp_change_completes_frame() {
+		let mut assembler = FrameAssembler::new();
+
+		assembler.push(&header(1, 100, 1, false));
+		assembler.push(&header(2, 100, 1, false));
+		assembler.push(&header(3, 200, 1, true));
+
+		let first = assembler.next_frame().unwrap();
+		assert_eq!(first.sequence_range(), (1, 2));
+		assert!(!first.has_marker());
+
+		let second = assembler.next_frame().unwrap();
+		assert_eq!(second.sequence_range(), (3, 3));
+		assert!(second.has_marker());
+	}
+
+	#[test]
+	fn ssrc_change_completes_frame() {
+		let mut assembler = FrameAssembler::new();
+
+		assembler.push(&header(1, 100, 1, false));
+		assembler.push(&header(50, 100, 2, false));
+
+		assert_eq!(assembler.next_frame().unwrap().ssrc(), 1);
+		assert_eq!(assembler.flush().unwrap().ssrc(), 2);
+	}
+
+	#[test]
+	fn fragment_payload() {
+		let header = header(65534, 100, 1, true);
+		let payload : Vec<u8> = (0..10).collect();
+
+		let packets = fragment(&header, &payload, 4);
+
+		assert_eq!(packets.len(), 3);
+		let seqs : Vec<u16> = packets.iter().map(|p| p.header().sequence()).collect();
+		assert_eq!(seqs, vec![65534, 65535, 0]);
+		let markers : Vec<bool> = packets.iter().map(|p| p.header().has_marker()).collect();
+		assert_eq!(markers, vec![false, false, true]);
+		assert!(packets.iter().all(|p| p.header().timestamp() == 100));
+
+		assert_eq!(packets[0].payload(), &[0, 1, 2, 3]);
+		assert_eq!(packets[2].payload(), &[8, 9]);
+	}
+
+	#[test]
+	fn fragment_small_and_empty_payloads() {
+		let header = header(1, 100, 1, false);
+
+		let packets = fragment(&header, &[1, 2, 3, 4], 4);
+		assert_eq!(packets.len(), 1);
+		assert!(packets[0].header().has_marker());
+
+		let packets = fragment(&header, &[], 4);
+		assert_eq!(packets.len(), 1);
+		assert!(packets[0].payload().is_empty());
+		assert!(packets[0].header().has_marker());
+	}
+
+	#[test]
+	fn fragment_clears_padding() {
+		let header = HeaderBuilder::new().padding(true).sequence(1).build().unwrap();
+		let packets = fragment(&header, &[1, 2, 3, 4, 5], 2);
+
+		assert_eq!(packets.len(), 3);
+		for packet in &packets {
+			assert!(!packet.header().has_padding());
+			let buf = packet.to_vec().unwrap();
+			assert_eq!(&Packet::from_buf(&buf).unwrap(), packet);
+		}
+	}
+
+	#[test]
+	fn reassemble_out_of_order() {
+		let payload : Vec<u8> = (0..10).collect();
+		let first = fragment(&header(65533, 50, 1, false), &payload, 5);
+		let packets = fragment(&header(65535, 100, 1, false), &payload, 4);
+
+		let mut reassembler = Reassembler::new();
+		reassembler.push(&first[0]);
+		reassembler.push(&first[1]);
+		assert_eq!(reassembler.next_frame().unwrap().timestamp(), 50);
+
+		reassembler.push(&packets[2]);
+		reassembler.push(&packets[0]);
+		reassembler.push(&packets[0]);
+		assert!(reassembler.next_frame().is_none());
+		assert_eq!(reassembler.pending(), 1);
+
+		reassembler.push(&packets[1]);
+		let frame = reassembler.next_frame().unwrap();
+		assert_eq!(frame.timestamp(), 100);
+		assert_eq!(frame.sequence_range(), (65535, 1));
+		assert_eq!(frame.payload(), &payload[..]);
+		assert_eq!(reassembler.pending(), 0);
+	}
+
+	#[test]
+	fn frame_waits_for_previous_end() {
+		let payload : Vec<u8> = (0..4).collect();
+		let start = fragment(&header(0, 50, 1, false), &[0xff], 2);
+		let first = fragment(&header(1, 100, 1, false), &payload, 2);
+		let second = fragment(&header(3, 200, 1, false), &payload, 2);
+
+		let mut reassembler = Reassembler::new();
+		reassembler.push(&start[0]);
+		assert_eq!(reassembler.next_frame().unwrap().sequence_range(), (0, 0));
+
+		// The second frame arrives whole before the end of the first.
+		reassembler.push(&first[0]);
+		reassembler.push(&second[0]);
+		reassembler.push(&second[1]);
+		assert!(reassembler.next_frame().is_none());
+
+		reassembler.push(&first[1]);
+		assert_eq!(reassembler.next_frame().unwrap().sequence_range(), (1, 2));
+		assert_eq!(reassembler.next_frame().unwrap().sequence_range(), (3, 4));
+
+		// The frame ending at 6 is lost, so the next cannot complete.
+		let last = fragment(&header(7, 400, 1, false), &payload, 2);
+		reassembler.push(&last[0]);
+		reassembler.push(&last[1]);
+		assert!(reassembler.next_frame().is_none());
+		assert_eq!(reassembler.pending(), 1);
+	}
+
+	#[test]
+	fn evict_incomplete_frames() {
+		let payload : Vec<u8> = (0..10).collect();
+		let first = fragment(&header(1, 100, 1, false), &payload, 2);
+		let second = fragment(&header(6, 200, 1, false), &payload, 5);
+
+		let mut reassembler = Reassembler::new();
+		reassembler.push(&first[0]);
+		reassembler.push(&first[3]);
+		reassembler.push(&first[4]);
+		reassembler.push(&second[0]);
+		reassembler.push(&second[1]);
+		assert_eq!(reassembler.next_frame().unwrap().timestamp(), 200);
+
+		let evicted = reassembler.evict_older_than(200);
+		assert_eq!(evicted.len(), 1);
+		assert_eq!(evicted[0].timestamp(), 100);
+		assert_eq!(evicted[0].received(), 3);
+		assert_eq!(evicted[0].missing(), &[2, 3]);
+		assert!(evicted[0].has_marker());
+		assert_eq!(reassembler.pending(), 0);
+	}
+
+	#[test]
+	fn frames_complete_in_reverse_order() {
+		let payload : Vec<u8> = (0..4).collect();
+		let start = fragment(&header(0, 50, 1, false), &[0xff], 2);
+		let frames = [
+			fragment(&header(1, 100, 1, false), &payload, 2),
+			fragment(&header(3, 200, 1, false), &payload, 2),
+			fragment(&header(5, 300, 1, false), &payload, 2),
+		];
+
+		let mut reassembler = Reassembler::new();
+		reassembler.push(&start[0]);
+		assert!(reassembler.next_frame().is_some());
+
+		for frame in frames.iter().rev() {
+			reassembler.push(&frame[1]);
+			reassembler.push(&frame[0]);
+		}
+
+		let timestamps : Vec<u32> = (0..3).filter_map(|_| reassembler.next_frame()).map(|f| f.timestamp()).collect();
+		assert_eq!(timestamps, vec![300, 200, 100]);
+		assert_eq!(reassembler.pending(), 0);
+	}
+
+	#[test]
+	fn pending_frames_are_capped() {
+		let mut reassembler = Reassembler::new();
+		for i in 0..MAX_PENDING_FRAMES as u32 + 1 {
+			// Every frame is missing its second packet.
+			let packets = fragment(&header(i as u16 * 2, i * 100, 1, false), &[0, 1], 1);
+			reassembler.push(&packets[0]);
+		}
+
+		assert_eq!(reassembler.pending(), MAX_PENDING_FRAMES);
+		let evicted = reassembler.evict_older_than(u32::MAX / 2);
+		assert_eq!(evicted.len(), MAX_PENDING_FRAMES);
+		assert_eq!(evicted[0].timestamp(), 100);
+	}
+
+	#[test]
+	fn reordered_across_wrap() {
+		let mut assembler = FrameAssembler::new();
+
+		assembler.push(&header(0, 100, 1, false));
+		assembler.push(&header(65535, 100, 1, false));
+		assembler.push(&header(1, 100, 1, true));
+
+		assert_eq!(assembler.next_frame().unwrap().sequence_range(), (65535, 1));
+	}
+}
@@ -1,252 +1,2784 @@
-/// The RtpHeader module.
+//! The RtpHeader module.
+//!
+//! This module provides an implementation of the RtpHeader per [RFC-1889](https://tools.ietf.org/html/rfc1889).
+//! The module is to be used to construct RtpHeaders from incoming network buffers.
+//!
+//! The RTP header has the following format:
+//!
+//! 0                   1                   2                   3
+//! 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |V=2|P|X|  CC   |M|     PT      |       sequence number         |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |                           timestamp                           |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |           synchronization source (SSRC) identifier            |
+//! +=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+
+//! |            contributing source (CSRC) identifiers             |
+//! |                             ....                              |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |                       Extension Header                        |
+//! |                             ....                              |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+use byteorder::{ByteOrder, NetworkEndian};
+use super::RtpError;
+use super::extension::{self, ExtensionElement, ExtensionProfile};
+use super::payload_type::PayloadType;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::time::Duration;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+use core::convert::TryFrom;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The default maximum number of 32 bit blocks accepted in a header
+/// extension.
 ///
-/// This module provides an implementation of the RtpHeader per [RFC-1889](https://tools.ietf.org/html/rfc1889).
-/// The module is to be used to construct RtpHeaders from incoming network buffers.
+/// The EHL field allows up to 65535 blocks (256 KiB) which no real packet
+/// carries. Parsing rejects extensions above this bound unless the caller
+/// opts in to a larger limit.
+pub const DEFAULT_MAX_EXTENSION_WORDS: u16 = 1024;
+
+/// Options for `Header::from_buf_with_config`.
 ///
-/// The RTP header has the following format:
+/// Some profiles and legacy equipment always send a header extension of
+/// a fixed size. Setting `expected_extension_words` makes any other
+/// extension length a parse error.
 ///
-/// 0                   1                   2                   3
-/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
-/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-/// |V=2|P|X|  CC   |M|     PT      |       sequence number         |
-/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-/// |                           timestamp                           |
-/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-/// |           synchronization source (SSRC) identifier            |
-/// +=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+=+
-/// |            contributing source (CSRC) identifiers             |
-/// |                             ....                              |
-/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-/// |                       Extension Header                        |
-/// |                             ....                              |
-/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// Conformance tools may also set `reject_unflagged_extension` to catch
+/// senders which write an extension without setting the extension flag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserConfig {
+	/// The number of 32 bit blocks every header extension must contain.
+	pub expected_extension_words: Option<u16>,
+	/// Reject headers without the extension flag which are followed by
+	/// what looks like an RFC-5285 extension: a one-byte or two-byte
+	/// profile id and a length which fits in the rest of the buffer.
+	pub reject_unflagged_extension: bool,
+}
+
+/// Returns true if the buffer begins with a plausible RFC-5285 extension
+/// header.
+fn looks_like_extension(buf: &[u8]) -> bool {
+	if buf.len() < 4 {
+		return false;
+	}
+
+	let ehl = NetworkEndian::read_u16(&buf[2..]) as usize;
+	let known_profile = match ExtensionProfile::from_id(NetworkEndian::read_u16(buf)) {
+		ExtensionProfile::OneByte | ExtensionProfile::TwoByte => true,
+		ExtensionProfile::Other(_) => false,
+	};
+
+	known_profile && 4 + ehl * 4 <= buf.len()
+}
+
+/// The header for the RTP packet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Header {
+	info: HeaderInfo,
+	sequence: u16,
+	timestamp: u32,
+	ssrc_identifier: u32,
+	csrc_identifiers: CSRCIdentifiers,
+	extension: Option<HeaderExtension>,
+}
+
+impl Header {
+	/// Constructs a version 2 header with all flags clear, no CSRC
+	/// identifiers and no extension.
+	///
+	/// Only the low 7 bits of the payload type are used. Use `HeaderBuilder`
+	/// for anything more involved.
+	pub fn new(payload_type: u8, sequence: u16, timestamp: u32, ssrc: u32) -> Self {
+		let mut info = HeaderInfo::default();
+		info.set_payload_type(payload_type);
+
+		Header {
+			info,
+			sequence,
+			timestamp,
+			ssrc_identifier: ssrc,
+			csrc_identifiers: CSRCIdentifiers::new(),
+			extension: None,
+		}
+	}
+
+	/// Construct the header from a network buffer.
+	/// Note the buffer will be Big-Endian.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error.
+	pub fn from_buf(header_buf: &[u8]) -> Result<Self, RtpError> {
+		Header::from_buf_with_len(header_buf).map(|(header, _)| header)
+	}
+
+	/// Construct the header from a `Bytes` buffer.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error.
+	#[cfg(feature = "bytes")]
+	pub fn from_bytes(buf: &Bytes) -> Result<Self, RtpError> {
+		Header::from_buf(buf)
+	}
+
+	/// Construct the header from a network buffer, also returning the
+	/// total length of the header in bytes.
+	///
+	/// The length covers the fixed header, the CSRC identifiers and the
+	/// extension (if present) - it is the offset at which the payload begins.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error.
+	pub fn from_buf_with_len(header_buf: &[u8]) -> Result<(Self, usize), RtpError> {
+		Header::parse(header_buf, DEFAULT_MAX_EXTENSION_WORDS)
+	}
+
+	/// Construct the header from a network buffer, also returning the
+	/// offset and length in bytes of the header extension within the buffer.
+	///
+	/// The range covers the whole extension, starting with its 4 byte
+	/// extension id and length, so callers can interpret custom profiles
+	/// themselves. It is `None` when the header has no extension.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error.
+	pub fn from_buf_with_extension_range(header_buf: &[u8]) -> Result<(Self, Option<(usize, usize)>), RtpError> {
+		let header = Header::from_buf(header_buf)?;
+		let range = header.extension.as_ref().map(|ext| {
+			(12 + header.csrc_identifiers.len() * 4, 4 + ext.ehl as usize * 4)
+		});
+
+		Ok((header, range))
+	}
+
+	/// Returns the offset at which the payload begins in a network buffer.
+	///
+	/// This checks that the buffer contains the full header declared by
+	/// its CSRC count and extension length, without allocating. For a
+	/// well-formed packet it matches `byte_len` of the parsed header.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error.
+	pub fn payload_offset(buf: &[u8]) -> Result<usize, RtpError> {
+		HeaderView::from_buf(buf).map(|view| view.byte_len())
+	}
+
+	/// Returns the 12 byte fixed header of a network buffer, for example to
+	/// hash or authenticate it.
+	///
+	/// # Errors
+	///
+	/// If the buffer is too small to contain the fixed header this method
+	/// will return an Error.
+	pub fn fixed_bytes(buf: &[u8]) -> Result<&[u8], RtpError> {
+		if buf.len() < 12 {
+			return Err(RtpError::BufferTooSmall { needed: 12, got: buf.len() });
+		}
+
+		Ok(&buf[..12])
+	}
+
+	/// Returns the fixed header of a network buffer followed by its CSRC
+	/// identifiers, excluding any extension.
+	///
+	/// # Errors
+	///
+	/// If the buffer is too small to contain the fixed header or the
+	/// declared CSRC identifiers this method will return an Error.
+	pub fn fixed_and_csrc_bytes(buf: &[u8]) -> Result<&[u8], RtpError> {
+		let info = HeaderInfo(NetworkEndian::read_u16(Header::fixed_bytes(buf)?));
+		let len = 12 + info.csrc_count() as usize * 4;
+		if buf.len() < len {
+			return Err(RtpError::TruncatedCsrc {
+				declared: info.csrc_count(),
+				available_words: (buf.len() - 12) / 4,
+				offset: 12,
+			});
+		}
+
+		Ok(&buf[..len])
+	}
+
+	/// Returns the number of bytes in a network buffer following the
+	/// header, which is zero when the buffer contains only a header.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error.
+	pub fn remaining_after_header(buf: &[u8]) -> Result<usize, RtpError> {
+		Header::payload_offset(buf).map(|offset| buf.len() - offset)
+	}
+
+	/// Returns the number of padding bytes at the end of a network buffer,
+	/// or `None` if the padding flag is clear.
+	///
+	/// The count includes the trailing length byte itself. The padding is
+	/// left in place.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error. If the padding flag is set but the
+	/// padding length is zero or longer than the data following the header
+	/// this method will return `RtpError::InvalidPadding`.
+	pub fn padding_len(buf: &[u8]) -> Result<Option<u8>, RtpError> {
+		let view = HeaderView::from_buf(buf)?;
+		if !view.info().has_padding() {
+			return Ok(None);
+		}
+
+		let offset = view.byte_len();
+		if buf.len() == offset {
+			return Err(RtpError::InvalidPadding);
+		}
+
+		// The last byte of the padding contains the number of padding
+		// bytes (including itself).
+		let padding = buf[buf.len() - 1];
+		if padding == 0 || padding as usize > buf.len() - offset {
+			return Err(RtpError::InvalidPadding);
+		}
+
+		Ok(Some(padding))
+	}
+
+	/// Construct the header from a network buffer, accepting header
+	/// extensions of up to `max_extension_words` 32 bit blocks rather than
+	/// `DEFAULT_MAX_EXTENSION_WORDS`.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// or the extension is longer than the limit this method will return an Error.
+	pub fn from_buf_with_extension_limit(header_buf: &[u8], max_extension_words: u16) -> Result<Self, RtpError> {
+		Header::parse(header_buf, max_extension_words).map(|(header, _)| header)
+	}
+
+	/// Construct the header from a network buffer using the parser options
+	/// in `config`.
+	///
+	/// When `config.expected_extension_words` is set the extension length
+	/// is checked before the extension is read. Headers without an
+	/// extension are accepted.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889),
+	/// the extension length differs from the expected length, or an
+	/// extension follows a header without the extension flag when
+	/// `config.reject_unflagged_extension` is set, this method will return
+	/// an Error.
+	pub fn from_buf_with_config(header_buf: &[u8], config: &ParserConfig) -> Result<Self, RtpError> {
+		if let Some(expected) = config.expected_extension_words {
+			if header_buf.len() >= 12 {
+				let info = HeaderInfo(NetworkEndian::read_u16(header_buf));
+				let ehl_offset = 12 + info.csrc_count() as usize * 4 + 2;
+				if info.has_extension() && header_buf.len() >= ehl_offset + 2
+					&& NetworkEndian::read_u16(&header_buf[ehl_offset..]) != expected {
+					return Err(RtpError::HeaderError("Header extension length does not match the expected length."));
+				}
+			}
+		}
+
+		let max_extension_words = config.expected_extension_words.unwrap_or(DEFAULT_MAX_EXTENSION_WORDS);
+		let (header, len) = Header::parse(header_buf, max_extension_words)?;
+
+		if config.reject_unflagged_extension && !header.has_extension() && looks_like_extension(&header_buf[len..]) {
+			return Err(RtpError::HeaderError("Header extension present without the extension flag."));
+		}
+
+		Ok(header)
+	}
+
+	/// Construct the header from the first `valid_len` bytes of a network
+	/// buffer, such as a receive buffer partially filled by `recv_from`.
+	///
+	/// Bytes past `valid_len` are never read, so a header declaring more
+	/// CSRC identifiers or extension than was received is an error rather
+	/// than being filled from stale data.
+	///
+	/// # Errors
+	///
+	/// If `valid_len` is greater than the buffer, or the header does not fit
+	/// the format per [RFC-1889](https://tools.ietf.org/html/rfc1889) within
+	/// `valid_len` bytes, this method will return an Error.
+	pub fn from_buf_len(header_buf: &[u8], valid_len: usize) -> Result<Self, RtpError> {
+		if valid_len > header_buf.len() {
+			return Err(RtpError::BufferTooSmall { needed: valid_len, got: header_buf.len() });
+		}
+
+		Header::from_buf(&header_buf[..valid_len])
+	}
+
+	/// Construct the header from a network buffer, rejecting headers whose
+	/// declared length (including the CSRC identifiers and extension) is
+	/// greater than `max_header_len` bytes.
+	///
+	/// The length is checked before anything is allocated.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// or is longer than the bound this method will return an Error.
+	pub fn from_buf_bounded(header_buf: &[u8], max_header_len: usize) -> Result<Self, RtpError> {
+		if Header::payload_offset(header_buf)? > max_header_len {
+			return Err(RtpError::HeaderError("Header length exceeds the allowed maximum."));
+		}
+
+		Header::from_buf(header_buf)
+	}
+
+	/// Construct the header from a network buffer, rejecting packets
+	/// longer than `max_total` bytes or whose declared header length
+	/// (including the CSRC identifiers and extension) is longer.
+	///
+	/// This is a single blanket limit for servers, checked from the first
+	/// bytes of the header before anything is allocated.
+	///
+	/// # Errors
+	///
+	/// If the packet or its declared header is longer than the bound, or
+	/// the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889),
+	/// this method will return an Error.
+	pub fn from_buf_max(header_buf: &[u8], max_total: usize) -> Result<Self, RtpError> {
+		if header_buf.len() > max_total {
+			return Err(RtpError::HeaderError("Packet length exceeds the allowed maximum."));
+		}
+
+		if header_buf.len() >= 12 {
+			let info = HeaderInfo(NetworkEndian::read_u16(header_buf));
+			let mut declared = 12 + info.csrc_count() as usize * 4;
+			if info.has_extension() && header_buf.len() >= declared + 4 {
+				declared += 4 + NetworkEndian::read_u16(&header_buf[declared + 2..]) as usize * 4;
+			}
+
+			if declared > max_total {
+				return Err(RtpError::HeaderError("Header length exceeds the allowed maximum."));
+			}
+		}
+
+		Header::from_buf(header_buf)
+	}
+
+	/// Parses the header and its length from the buffer.
+	fn parse(mut header_buf: &[u8], max_extension_words: u16) -> Result<(Self, usize), RtpError> {
+		if header_buf.len() < 12 {
+			return Err(RtpError::BufferTooSmall { needed: 12, got: header_buf.len() });
+		}
+		// Extract the static header parts from 0..96 bits
+		// Get the 16 bits for info
+		let info = HeaderInfo(NetworkEndian::read_u16(header_buf));
+		header_buf = &header_buf[2..];
+		
+		// Get bits for sequence
+		let sequence = NetworkEndian::read_u16(header_buf);
+		header_buf = &header_buf[2..];
+
+		// Timestamp is 32 bits
+		let timestamp = NetworkEndian::read_u32(header_buf);
+		header_buf = &header_buf[4..];
+
+		// SSRCS id is 32 bits
+		let ssrc_id = NetworkEndian::read_u32(header_buf);
+		header_buf = &header_buf[4..];
+
+		// Get the number of csrc identifiers from the header info
+		let csrc_count = info.csrc_count() as usize;
+
+		// Check that we have room for the CSRC in buffer
+		if header_buf.len() < csrc_count * 4 {
+			return Err(RtpError::TruncatedCsrc {
+				declared: info.csrc_count(),
+				available_words: header_buf.len() / 4,
+				offset: 12,
+			});
+		}
+
+		// Pull the csrc identifiers from the header. They are stored inline
+		// so this never touches the allocator.
+		let csrc_ids = CSRCIdentifiers::from_buf(&header_buf[..csrc_count * 4]);
+		header_buf = &header_buf[csrc_count * 4..];
+		let mut header_len = 12 + csrc_count * 4;
+
+		// Extract the headers
+		let extension = if info.has_extension() {
+			// We try to build the extension from the buffer - will return
+			// an error if format is invalid.
+			let ext = HeaderExtension::from_buf_with_limit(header_buf, max_extension_words)
+				.map_err(|e| e.shift_offset(header_len))?;
+			header_len += 4 + ext.ehl as usize * 4;
+			Some(ext)
+		} else {
+			None
+		};
+
+		Ok((Header {
+			info,
+			sequence,
+			timestamp,
+			ssrc_identifier: ssrc_id,
+			csrc_identifiers: csrc_ids,
+			extension,
+		}, header_len))
+	}
+
+	/// Construct the header from a network buffer, salvaging as much of
+	/// the header as possible.
+	///
+	/// The fixed header fields are always returned when present. If the
+	/// CSRC identifiers or the extension are malformed they are left empty
+	/// (or `None`) and the problem is reported alongside the header. The
+	/// header info is kept as it was on the wire.
+	///
+	/// # Errors
+	///
+	/// If the buffer is too small to contain the 12 byte fixed header this
+	/// method will return an Error.
+	pub fn from_buf_lenient(header_buf: &[u8]) -> Result<(Self, Option<RtpError>), RtpError> {
+		if header_buf.len() < 12 {
+			return Err(RtpError::BufferTooSmall { needed: 12, got: header_buf.len() });
+		}
+
+		let info = HeaderInfo(NetworkEndian::read_u16(header_buf));
+		let has_extension = info.has_extension();
+		let csrc_len = info.csrc_count() as usize * 4;
+
+		let mut header = Header {
+			info,
+			sequence: NetworkEndian::read_u16(&header_buf[2..]),
+			timestamp: NetworkEndian::read_u32(&header_buf[4..]),
+			ssrc_identifier: NetworkEndian::read_u32(&header_buf[8..]),
+			csrc_identifiers: CSRCIdentifiers::new(),
+			extension: None,
+		};
+
+		let header_buf = &header_buf[12..];
+		if header_buf.len() < csrc_len {
+			let err = RtpError::TruncatedCsrc {
+				declared: header.info.csrc_count(),
+				available_words: header_buf.len() / 4,
+				offset: 12,
+			};
+			return Ok((header, Some(err)));
+		}
+		header.csrc_identifiers = CSRCIdentifiers::from_buf(&header_buf[..csrc_len]);
+
+		if has_extension {
+			match HeaderExtension::from_buf(&header_buf[csrc_len..]) {
+				Ok(ext) => header.extension = Some(ext),
+				Err(e) => return Ok((header, Some(e.shift_offset(12 + csrc_len)))),
+			}
+		}
+
+		Ok((header, None))
+	}
+
+	/// Construct the header from a network buffer, rejecting any
+	/// header which is not RTP version 2.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// or the version is not 2 this method will return an Error.
+	pub fn from_buf_strict(header_buf: &[u8]) -> Result<Self, RtpError> {
+		let header = Header::from_buf(header_buf)?;
+
+		if header.info.version() != 2 {
+			return Err(RtpError::UnsupportedVersion(header.info.version()));
+		}
+
+		Ok(header)
+	}
+
+	/// Write the header into a network buffer.
+	/// The buffer will be written Big-Endian.
+	///
+	/// Returns the number of bytes written.
+	///
+	/// The CSRC count written is always the number of CSRC identifiers
+	/// in the header, so the output is self-consistent even if the count
+	/// in the header info is not (as with a header from `from_buf_lenient`).
+	///
+	/// # Errors
+	///
+	/// If the buffer is too small to hold the fixed header, the CSRC
+	/// identifiers and the extension this method will return an Error.
+	pub fn to_buf(&self, out: &mut [u8]) -> Result<usize, RtpError> {
+		let csrc_ids = self.csrc_identifiers.as_slice();
+		let header_len = self.byte_len();
+
+		if out.len() < header_len {
+			return Err(RtpError::BufferTooSmall { needed: header_len, got: out.len() });
+		}
+
+		let mut info = self.info.clone();
+		info.set_csrc_count(csrc_ids.len() as u8);
+
+		NetworkEndian::write_u16(&mut out[0..], info.0);
+		NetworkEndian::write_u16(&mut out[2..], self.sequence);
+		NetworkEndian::write_u32(&mut out[4..], self.timestamp);
+		NetworkEndian::write_u32(&mut out[8..], self.ssrc_identifier);
+
+		let mut offset = 12;
+		for csrc in csrc_ids {
+			NetworkEndian::write_u32(&mut out[offset..], *csrc);
+			offset += 4;
+		}
+
+		if let Some(ref ext) = self.extension {
+			offset += ext.to_buf(&mut out[offset..])?;
+		}
+
+		Ok(offset)
+	}
+
+	/// Serializes the header into a new network buffer of `byte_len`
+	/// bytes.
+	///
+	/// # Errors
+	///
+	/// If the extension data does not match the extension header length
+	/// this method will return an Error.
+	pub fn to_vec(&self) -> Result<Vec<u8>, RtpError> {
+		let mut buf = vec![0u8; self.byte_len()];
+		let len = self.to_buf(&mut buf)?;
+		buf.truncate(len);
+		Ok(buf)
+	}
+
+	/// Returns the size of the header on the wire in bytes.
+	///
+	/// This is the 12 byte fixed header, 4 bytes per CSRC identifier and,
+	/// if present, the 4 byte extension header plus 4 bytes per extension
+	/// block.
+	pub fn byte_len(&self) -> usize {
+		let extension_len = match self.extension {
+			Some(ref ext) => 4 + ext.ehl as usize * 4,
+			None => 0,
+		};
+
+		12 + self.csrc_identifiers.len() * 4 + extension_len
+	}
+
+	/// Checks that the header is self-consistent and version 2.
+	///
+	/// # Errors
+	///
+	/// Returns an Error for the first of these which is violated:
+	///
+	/// - The version is 2.
+	/// - The CSRC count matches the number of CSRC identifiers.
+	/// - The extension flag matches the presence of an extension.
+	/// - The extension length matches the extension data.
+	pub fn validate(&self) -> Result<(), RtpError> {
+		if self.info.version() != 2 {
+			return Err(RtpError::UnsupportedVersion(self.info.version()));
+		}
+		if self.info.csrc_count() as usize != self.csrc_identifiers.len() {
+			return Err(RtpError::HeaderError("CSRC count does not match the CSRC identifiers."));
+		}
+		if self.info.has_extension() != self.extension.is_some() {
+			return Err(RtpError::HeaderError("Extension flag does not match the header extension."));
+		}
+		if let Some(ref ext) = self.extension {
+			if ext.ehl as usize != ext.extension.len() {
+				return Err(RtpError::HeaderError("Extension length does not match the extension data."));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Return the header info.
+	pub fn info(&self) -> &HeaderInfo {
+		&self.info
+	}
+
+	/// Gets the version from the header info.
+	pub fn version(&self) -> u8 {
+		self.info.version()
+	}
+
+	/// Gets the padding flag from the header info.
+	pub fn has_padding(&self) -> bool {
+		self.info.has_padding()
+	}
+
+	/// Gets the extension flag from the header info.
+	pub fn has_extension(&self) -> bool {
+		self.info.has_extension()
+	}
+
+	/// Gets the number of CSRC identifiers from the header info.
+	pub fn csrc_count(&self) -> u8 {
+		self.info.csrc_count()
+	}
+
+	/// Gets the marker flag from the header info.
+	pub fn has_marker(&self) -> bool {
+		self.info.has_marker()
+	}
+
+	/// Gets the payload type from the header info.
+	pub fn payload_type(&self) -> u8 {
+		self.info.payload_type()
+	}
+
+	/// Sets the marker flag in the header info.
+	pub fn set_marker(&mut self, marker: bool) {
+		self.info.set_marker(marker);
+	}
+
+	/// Sets the padding flag in the header info.
+	pub fn set_padding(&mut self, padding: bool) {
+		self.info.set_padding(padding);
+	}
+
+	/// Returns the sequence.
+	pub fn sequence(&self) -> u16 {
+		self.sequence
+	}
+
+	/// Sets the sequence.
+	pub fn set_sequence(&mut self, seq: u16) {
+		self.sequence = seq;
+	}
+
+	/// Returns the timestamp as a `u32`.
+	pub fn timestamp(&self) -> u32 {
+		self.timestamp
+	}
+
+	/// Sets the timestamp.
+	pub fn set_timestamp(&mut self, ts: u32) {
+		self.timestamp = ts;
+	}
+
+	/// Returns a copy of the header with the sequence replaced.
+	pub fn clone_with_sequence(&self, seq: u16) -> Header {
+		let mut header = self.clone();
+		header.sequence = seq;
+		header
+	}
+
+	/// Returns a copy of the header with the timestamp replaced.
+	pub fn clone_with_timestamp(&self, ts: u32) -> Header {
+		let mut header = self.clone();
+		header.timestamp = ts;
+		header
+	}
+
+	/// Returns the time elapsed between the timestamp of `other` and the
+	/// timestamp of this header, given the RTP clock rate in Hz.
+	///
+	/// The difference uses 32 bit serial arithmetic so that a small forward
+	/// step across the wrap is handled correctly. If `other` is newer than
+	/// this header a zero duration is returned.
+	///
+	/// # Panics
+	///
+	/// Panics if `clock_rate` is zero.
+	pub fn timestamp_duration_since(&self, other: &Header, clock_rate: u32) -> Duration {
+		let delta = self.timestamp.wrapping_sub(other.timestamp);
+		if (delta as i32) < 0 {
+			return Duration::from_secs(0);
+		}
+
+		let secs = delta / clock_rate;
+		let nanos = (delta % clock_rate) as u64 * 1_000_000_000 / clock_rate as u64;
+		Duration::new(secs as u64, nanos as u32)
+	}
+
+	/// Returns the SSRC identifier.
+	pub fn ssrc_identifier(&self) -> u32 {
+		self.ssrc_identifier
+	}
+
+	/// Sets the SSRC identifier.
+	pub fn set_ssrc_identifier(&mut self, ssrc: u32) {
+		self.ssrc_identifier = ssrc;
+	}
+
+	/// Returns the SSRC identifier and sequence, which identify a packet
+	/// within a session for duplicate detection.
+	pub fn dedup_key(&self) -> (u32, u16) {
+		(self.ssrc_identifier, self.sequence)
+	}
+
+	/// Returns the CSRC identifiers.
+	#[deprecated(note = "use `csrcs` to iterate over the CSRC identifiers")]
+	pub fn csrc_identifiers(&self) -> &[u32] {
+		self.csrc_identifiers.as_slice()
+	}
+
+	/// Returns an iterator over the CSRC identifiers.
+	pub fn csrcs(&self) -> impl Iterator<Item = u32> + '_ {
+		self.csrc_identifiers.as_slice().iter().cloned()
+	}
+
+	/// Appends a CSRC identifier, updating the CSRC count.
+	///
+	/// # Errors
+	///
+	/// If the header already contains 15 CSRC identifiers this method
+	/// will return an Error.
+	pub fn push_csrc(&mut self, ssrc: u32) -> Result<(), RtpError> {
+		self.csrc_identifiers.push(ssrc)?;
+		self.sync_csrc_count();
+		Ok(())
+	}
+
+	/// Removes all CSRC identifiers, updating the CSRC count.
+	pub fn clear_csrc(&mut self) {
+		self.csrc_identifiers.clear();
+		self.sync_csrc_count();
+	}
+
+	/// Removes the CSRC identifier at `idx`, updating the CSRC count.
+	///
+	/// Returns the removed identifier or `None` if `idx` is out of range.
+	pub fn remove_csrc(&mut self, idx: usize) -> Option<u32> {
+		let csrc = self.csrc_identifiers.remove(idx)?;
+		self.sync_csrc_count();
+		Some(csrc)
+	}
+
+	/// Updates the CSRC count in the header info to match the identifiers.
+	fn sync_csrc_count(&mut self) {
+		let count = self.csrc_identifiers.len() as u8;
+		self.info.set_csrc_count(count);
+	}
+
+	/// Return the header extension.
+	pub fn extension(&self) -> &Option<HeaderExtension> {
+		&self.extension
+	}
+
+	/// Return the header extension for editing in place.
+	pub fn extension_mut(&mut self) -> Option<&mut HeaderExtension> {
+		self.extension.as_mut()
+	}
+
+	/// Returns the scalar fields of the header as a plain value, for
+	/// example for telemetry.
+	pub fn summary(&self) -> HeaderSummary {
+		HeaderSummary {
+			version: self.info.version(),
+			payload_type: self.info.payload_type(),
+			marker: self.info.has_marker(),
+			sequence: self.sequence,
+			timestamp: self.timestamp,
+			ssrc: self.ssrc_identifier,
+			csrc_count: self.csrc_identifiers.len() as u8,
+			has_extension: self.extension.is_some(),
+		}
+	}
+}
+
+/// The scalar fields of a header, returned by `Header::summary`.
+///
+/// Unlike `Header` the summary is `Copy` and holds no CSRC identifiers or
+/// extension data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeaderSummary {
+	/// The RTP version.
+	pub version: u8,
+	/// The payload type.
+	pub payload_type: u8,
+	/// The marker flag.
+	pub marker: bool,
+	/// The sequence number.
+	pub sequence: u16,
+	/// The timestamp.
+	pub timestamp: u32,
+	/// The SSRC identifier.
+	pub ssrc: u32,
+	/// The number of CSRC identifiers.
+	pub csrc_count: u8,
+	/// Whether the header has an extension.
+	pub has_extension: bool,
+}
+
+impl fmt::Display for Header {
+	/// Formats a one line summary of the header, for example
+	/// `RTP v2 PT=96 M=1 seq=1234 ts=567890 ssrc=0xABCD12 csrc=[0x1, 0x2] ext=yes`.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "RTP v{} PT={} M={} seq={} ts={} ssrc={:#X} csrc=[",
+			self.info.version(),
+			self.info.payload_type(),
+			self.info.has_marker() as u8,
+			self.sequence,
+			self.timestamp,
+			self.ssrc_identifier)?;
+
+		for (i, csrc) in self.csrc_identifiers.as_slice().iter().enumerate() {
+			if i > 0 {
+				write!(f, ", ")?;
+			}
+			write!(f, "{:#X}", csrc)?;
+		}
+
+		write!(f, "] ext={}", if self.extension.is_some() { "yes" } else { "no" })
+	}
+}
+
+impl<'a> TryFrom<&'a [u8]> for Header {
+	type Error = RtpError;
+
+	fn try_from(buf: &'a [u8]) -> Result<Self, RtpError> {
+		Header::from_buf(buf)
+	}
+}
+
+impl<'a> TryFrom<&'a Header> for Vec<u8> {
+	type Error = RtpError;
+
+	fn try_from(header: &'a Header) -> Result<Self, RtpError> {
+		header.to_vec()
+	}
+}
+
+/// Writes a version 2 header with no CSRC identifiers or extension directly
+/// into a network buffer, without constructing a `Header`.
+///
+/// Only the low 7 bits of the payload type are used. Returns the number of
+/// bytes written, which is always 12.
+///
+/// # Errors
+///
+/// If the buffer is smaller than 12 bytes this method will return an Error.
+pub fn encode_header(out: &mut [u8], pt: u8, seq: u16, ts: u32, ssrc: u32, marker: bool) -> Result<usize, RtpError> {
+	if out.len() < 12 {
+		return Err(RtpError::BufferTooSmall { needed: 12, got: out.len() });
+	}
+
+	let mut info = HeaderInfo::default();
+	info.set_marker(marker);
+	info.set_payload_type(pt);
+
+	NetworkEndian::write_u16(&mut out[0..], info.0);
+	NetworkEndian::write_u16(&mut out[2..], seq);
+	NetworkEndian::write_u32(&mut out[4..], ts);
+	NetworkEndian::write_u32(&mut out[8..], ssrc);
+
+	Ok(12)
+}
+
+/// A builder for constructing a `Header` programmatically.
+///
+/// The builder defaults to an RTP version 2 header with all flags clear,
+/// no CSRC identifiers and no extension.
+#[derive(Debug)]
+pub struct HeaderBuilder {
+	version: u8,
+	padding: bool,
+	marker: bool,
+	payload_type: u8,
+	sequence: u16,
+	timestamp: u32,
+	ssrc_identifier: u32,
+	csrc_identifiers: Vec<u32>,
+	extension: Option<Result<HeaderExtension, RtpError>>,
+}
+
+impl HeaderBuilder {
+	/// Constructs a new builder for a version 2 header.
+	pub fn new() -> Self {
+		HeaderBuilder {
+			version: 2,
+			padding: false,
+			marker: false,
+			payload_type: 0,
+			sequence: 0,
+			timestamp: 0,
+			ssrc_identifier: 0,
+			csrc_identifiers: Vec::new(),
+			extension: None,
+		}
+	}
+
+	/// Sets the RTP version.
+	pub fn version(mut self, version: u8) -> Self {
+		self.version = version;
+		self
+	}
+
+	/// Sets the padding flag.
+	pub fn padding(mut self, padding: bool) -> Self {
+		self.padding = padding;
+		self
+	}
+
+	/// Sets the marker flag.
+	pub fn marker(mut self, marker: bool) -> Self {
+		self.marker = marker;
+		self
+	}
+
+	/// Sets the payload type.
+	pub fn payload_type(mut self, payload_type: u8) -> Self {
+		self.payload_type = payload_type;
+		self
+	}
+
+	/// Sets the sequence number.
+	pub fn sequence(mut self, sequence: u16) -> Self {
+		self.sequence = sequence;
+		self
+	}
+
+	/// Sets the timestamp.
+	pub fn timestamp(mut self, timestamp: u32) -> Self {
+		self.timestamp = timestamp;
+		self
+	}
+
+	/// Sets the SSRC identifier.
+	pub fn ssrc(mut self, ssrc: u32) -> Self {
+		self.ssrc_identifier = ssrc;
+		self
+	}
+
+	/// Adds a CSRC identifier.
+	pub fn add_csrc(mut self, csrc: u32) -> Self {
+		self.csrc_identifiers.push(csrc);
+		self
+	}
+
+	/// Sets the header extension from an extension id and the 32bit
+	/// chunks of extension data.
+	///
+	/// The extension is constructed with `HeaderExtension::new`, and any
+	/// error is returned from `build`.
+	pub fn extension(mut self, extension_id: u16, extension: Vec<u32>) -> Self {
+		self.extension = Some(HeaderExtension::new(extension_id, extension));
+		self
+	}
+
+	/// Builds the header.
+	///
+	/// # Errors
+	///
+	/// If any of the fields do not fit in their bits in the header
+	/// this method will return an Error.
+	pub fn build(self) -> Result<Header, RtpError> {
+		if self.version > 0b11 {
+			return Err(RtpError::HeaderError("Version does not fit in 2 bits."));
+		}
+		let csrc_identifiers = CSRCIdentifiers::from_slice(&self.csrc_identifiers)?;
+		if self.payload_type > 0b1111111 {
+			return Err(RtpError::HeaderError("Payload type does not fit in 7 bits."));
+		}
+		let extension = match self.extension {
+			Some(ext) => Some(ext?),
+			None => None,
+		};
+
+		let mut info = HeaderInfo(0);
+		info.set_version(self.version);
+		info.set_padding(self.padding);
+		info.set_extension(extension.is_some());
+		info.set_csrc_count(self.csrc_identifiers.len() as u8);
+		info.set_marker(self.marker);
+		info.set_payload_type(self.payload_type);
+
+		Ok(Header {
+			info,
+			sequence: self.sequence,
+			timestamp: self.timestamp,
+			ssrc_identifier: self.ssrc_identifier,
+			csrc_identifiers,
+			extension,
+		})
+	}
+}
+
+impl Default for HeaderBuilder {
+	fn default() -> Self {
+		HeaderBuilder::new()
+	}
+}
+
+/// The header info
+///
+/// These 16 bits contain information for the rest of the header.
+///
+/// With the `serde` feature the info is serialized as its named fields
+/// rather than the raw 16 bits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "HeaderInfoFields", try_from = "HeaderInfoFields"))]
+pub struct HeaderInfo(u16);
+
+impl HeaderInfo {
+	/// Constructs the header info from the raw 16 bits.
+	pub fn from_raw(v: u16) -> HeaderInfo {
+		HeaderInfo(v)
+	}
+
+	/// Gets the raw 16 bits of the header info.
+	pub fn raw(&self) -> u16 {
+		self.0
+	}
+
+	/// Gets the version from the header info.
+	pub fn version(&self) -> u8 {
+		(self.0 >> 14) as u8
+	}
+
+	/// Gets the padding flag from the header info.
+	pub fn has_padding(&self) -> bool {
+		((self.0 >> 13) & 0b1) == 1
+	}
+
+	/// Gets the extension flag from the header info.
+	pub fn has_extension(&self) -> bool {
+		((self.0 >> 12) & 0b1) == 1
+	}
+
+	/// Gets the number of CSRC identifiers in the header.
+	pub fn csrc_count(&self) -> u8 {
+		((self.0 >> 8) & 0b1111) as u8
+	}
+
+	/// Gets the marker flag from the header info.
+	pub fn has_marker(&self) -> bool {
+		((self.0 >> 7) & 0b1) == 1
+	}
+
+	/// Gets the payload type of the packet
+	pub fn payload_type(&self) -> u8 {
+		(self.0 & 0b1111111) as u8
+	}
+
+	/// Gets the payload type of the packet as a `PayloadType`.
+	pub fn payload_type_kind(&self) -> PayloadType {
+		PayloadType::from_u8(self.payload_type())
+	}
+
+	/// Returns true if the payload is comfort noise, the static payload
+	/// type 13 of [RFC-3551](https://tools.ietf.org/html/rfc3551).
+	pub fn is_comfort_noise(&self) -> bool {
+		self.payload_type_kind() == PayloadType::Cn
+	}
+
+	/// Returns true if the payload is a telephone-event (DTMF) per
+	/// [RFC-4733](https://tools.ietf.org/html/rfc4733). The event has a
+	/// dynamic payload type, so the negotiated `dtmf_pt` must be given.
+	pub fn is_telephone_event(&self, dtmf_pt: u8) -> bool {
+		self.payload_type() == dtmf_pt
+	}
+
+	/// Sets the `width` bits at `shift` to `value`, masking the value to
+	/// the width and leaving the other bits untouched.
+	fn set_bits(&mut self, shift: u16, width: u16, value: u16) {
+		let mask = ((1 << width) - 1) << shift;
+		self.0 = (self.0 & !mask) | ((value << shift) & mask);
+	}
+
+	/// Sets the version in the header info. Only the low 2 bits are used.
+	pub fn set_version(&mut self, version: u8) {
+		self.set_bits(14, 2, version as u16);
+	}
+
+	/// Sets the padding flag in the header info.
+	pub fn set_padding(&mut self, padding: bool) {
+		self.set_bits(13, 1, padding as u16);
+	}
+
+	/// Sets the extension flag in the header info.
+	pub fn set_extension(&mut self, extension: bool) {
+		self.set_bits(12, 1, extension as u16);
+	}
+
+	/// Sets the number of CSRC identifiers in the header info.
+	/// Only the low 4 bits are used.
+	pub fn set_csrc_count(&mut self, count: u8) {
+		self.set_bits(8, 4, count as u16);
+	}
+
+	/// Sets the marker flag in the header info.
+	pub fn set_marker(&mut self, marker: bool) {
+		self.set_bits(7, 1, marker as u16);
+	}
+
+	/// Sets the payload type in the header info. Only the low 7 bits are used.
+	pub fn set_payload_type(&mut self, payload_type: u8) {
+		self.set_bits(0, 7, payload_type as u16);
+	}
+
+	/// Gets the marker and payload type as the second octet of the header,
+	/// `(marker << 7) | payload_type`.
+	pub fn pt_byte(&self) -> u8 {
+		self.0 as u8
+	}
+
+	/// Sets the marker and payload type from the second octet of the header.
+	pub fn set_pt_byte(&mut self, pt_byte: u8) {
+		self.set_bits(0, 8, pt_byte as u16);
+	}
+}
+
+impl Default for HeaderInfo {
+	/// Returns the info for a version 2 header with all other fields zero.
+	fn default() -> Self {
+		HeaderInfo(0b10 << 14)
+	}
+}
+
+/// The expanded fields of the header info used for serialization.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct HeaderInfoFields {
+	version: u8,
+	padding: bool,
+	extension: bool,
+	csrc_count: u8,
+	marker: bool,
+	payload_type: u8,
+}
+
+#[cfg(feature = "serde")]
+impl From<HeaderInfo> for HeaderInfoFields {
+	fn from(info: HeaderInfo) -> Self {
+		HeaderInfoFields {
+			version: info.version(),
+			padding: info.has_padding(),
+			extension: info.has_extension(),
+			csrc_count: info.csrc_count(),
+			marker: info.has_marker(),
+			payload_type: info.payload_type(),
+		}
+	}
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<HeaderInfoFields> for HeaderInfo {
+	type Error = RtpError;
+
+	fn try_from(fields: HeaderInfoFields) -> Result<Self, RtpError> {
+		if fields.version > 0b11 {
+			return Err(RtpError::HeaderError("Version does not fit in 2 bits."));
+		}
+		if fields.csrc_count > 15 {
+			return Err(RtpError::HeaderError("Too many CSRC identifiers, at most 15 are allowed."));
+		}
+		if fields.payload_type > 0b1111111 {
+			return Err(RtpError::HeaderError("Payload type does not fit in 7 bits."));
+		}
+
+		let mut info = HeaderInfo(0);
+		info.set_version(fields.version);
+		info.set_padding(fields.padding);
+		info.set_extension(fields.extension);
+		info.set_csrc_count(fields.csrc_count);
+		info.set_marker(fields.marker);
+		info.set_payload_type(fields.payload_type);
+		Ok(info)
+	}
+}
+
+/// The CSRC identifiers
+///
+/// These are the contributing source IDs for when stream has been
+/// generated from multiple sources.
+///
+/// At most 15 identifiers fit in a header, so they are stored inline
+/// rather than allocated.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "Vec<u32>", try_from = "Vec<u32>"))]
+pub struct CSRCIdentifiers {
+	identifiers: [u32; MAX_CSRC_COUNT],
+	len: u8,
+}
+
+/// The maximum number of CSRC identifiers, limited by the 4 bit count.
+const MAX_CSRC_COUNT: usize = 15;
+
+impl CSRCIdentifiers {
+	/// Constructs an empty list.
+	fn new() -> Self {
+		CSRCIdentifiers {
+			identifiers: [0; MAX_CSRC_COUNT],
+			len: 0,
+		}
+	}
+
+	/// Reads the identifiers from a buffer of whole 32 bit words, which
+	/// must hold at most 15 of them.
+	fn from_buf(buf: &[u8]) -> Self {
+		let mut csrcs = CSRCIdentifiers::new();
+		for (slot, chunk) in csrcs.identifiers.iter_mut().zip(buf.chunks(4)) {
+			*slot = NetworkEndian::read_u32(chunk);
+		}
+		csrcs.len = (buf.len() / 4) as u8;
+		csrcs
+	}
+
+	/// Constructs the list from a slice of identifiers.
+	fn from_slice(identifiers: &[u32]) -> Result<Self, RtpError> {
+		if identifiers.len() > MAX_CSRC_COUNT {
+			return Err(RtpError::HeaderError("Too many CSRC identifiers, at most 15 are allowed."));
+		}
+
+		let mut csrcs = CSRCIdentifiers::new();
+		csrcs.identifiers[..identifiers.len()].copy_from_slice(identifiers);
+		csrcs.len = identifiers.len() as u8;
+		Ok(csrcs)
+	}
+
+	/// Returns the populated identifiers.
+	fn as_slice(&self) -> &[u32] {
+		&self.identifiers[..self.len as usize]
+	}
+
+	fn len(&self) -> usize {
+		self.len as usize
+	}
+
+	fn push(&mut self, csrc: u32) -> Result<(), RtpError> {
+		if self.len() >= MAX_CSRC_COUNT {
+			return Err(RtpError::HeaderError("Too many CSRC identifiers, at most 15 are allowed."));
+		}
+
+		self.identifiers[self.len()] = csrc;
+		self.len += 1;
+		Ok(())
+	}
+
+	fn clear(&mut self) {
+		self.len = 0;
+	}
+
+	fn remove(&mut self, idx: usize) -> Option<u32> {
+		if idx >= self.len() {
+			return None;
+		}
+
+		let len = self.len();
+		let csrc = self.identifiers[idx];
+		self.identifiers.copy_within(idx + 1..len, idx);
+		self.len -= 1;
+		Some(csrc)
+	}
+}
+
+// The unused slots are ignored by the comparison, hash and debug output.
+
+impl PartialEq for CSRCIdentifiers {
+	fn eq(&self, other: &Self) -> bool {
+		self.as_slice() == other.as_slice()
+	}
+}
+
+impl Eq for CSRCIdentifiers {}
+
+impl Hash for CSRCIdentifiers {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.as_slice().hash(state)
+	}
+}
+
+impl fmt::Debug for CSRCIdentifiers {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("CSRCIdentifiers").field("identifiers", &self.as_slice()).finish()
+	}
+}
+
+impl AsRef<[u32]> for CSRCIdentifiers {
+	fn as_ref(&self) -> &[u32] {
+		self.as_slice()
+	}
+}
+
+#[cfg(feature = "serde")]
+impl From<CSRCIdentifiers> for Vec<u32> {
+	fn from(csrcs: CSRCIdentifiers) -> Self {
+		csrcs.as_slice().to_vec()
+	}
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Vec<u32>> for CSRCIdentifiers {
+	type Error = RtpError;
+
+	fn try_from(identifiers: Vec<u32>) -> Result<Self, RtpError> {
+		CSRCIdentifiers::from_slice(&identifiers)
+	}
+}
+
+/// The header extension.
+///
+/// This contains the extension id, the extension length, and the extension
+/// data as 32bit chunks. The raw bytes are encoded from the chunks when
+/// asked for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "HeaderExtensionFields", try_from = "HeaderExtensionFields"))]
+pub struct HeaderExtension {
+	extension_id: u16,
+	ehl: u16,
+	extension: Vec<u32>,
+}
+
+impl HeaderExtension {
+	/// Constructs a HeaderExtension from an extension id and the 32bit
+	/// chunks of extension data. The extension header length is set from
+	/// the number of chunks.
+	///
+	/// # Errors
+	///
+	/// If there are more than 65535 chunks, which the extension header
+	/// length cannot represent, this method will return an Error.
+	pub fn new(extension_id: u16, extension: Vec<u32>) -> Result<Self, RtpError> {
+		if extension.len() > u16::MAX as usize {
+			return Err(RtpError::HeaderError("Header extension contains too many blocks."));
+		}
+
+		Ok(HeaderExtension::from_blocks(extension_id, extension))
+	}
+
+	/// Constructs a HeaderExtension from 32bit chunks, filling in the
+	/// length.
+	fn from_blocks(extension_id: u16, extension: Vec<u32>) -> Self {
+		HeaderExtension {
+			extension_id,
+			ehl: extension.len() as u16,
+			extension,
+		}
+	}
+
+	/// Constructs a HeaderExtension from a network buffer.
+	///
+	/// Extensions longer than `DEFAULT_MAX_EXTENSION_WORDS` are rejected.
+	/// Error offsets are relative to the start of `extension_buf`.
+	pub fn from_buf(extension_buf: &[u8]) -> Result<Self, RtpError> {
+		HeaderExtension::from_buf_with_limit(extension_buf, DEFAULT_MAX_EXTENSION_WORDS)
+	}
+
+	/// Constructs a HeaderExtension from a network buffer, rejecting
+	/// extensions longer than `max_words` 32 bit blocks.
+	pub fn from_buf_with_limit(mut extension_buf: &[u8], max_words: u16) -> Result<Self, RtpError> {
+		if extension_buf.len() < 4 {
+			return Err(RtpError::TruncatedExtension { offset: 0 });
+		}
+
+		let id = NetworkEndian::read_u16(extension_buf);
+		extension_buf = &extension_buf[2..];
+		let ehl = NetworkEndian::read_u16(extension_buf);
+		extension_buf = &extension_buf[2..];
+
+		// Check the declared length before trusting it for an allocation.
+		if ehl > max_words {
+			return Err(RtpError::HeaderError("Header extension length exceeds the allowed maximum."));
+		}
+
+		if extension_buf.len() < ehl as usize * 4 {
+			return Err(RtpError::TruncatedExtension { offset: 0 });
+		}
+
+		let extension_data : Vec<u32> = extension_buf[..ehl as usize * 4]
+			.chunks(4)
+			.map(NetworkEndian::read_u32)
+			.collect();
+
+		Ok(HeaderExtension {
+			extension_id: id,
+			ehl,
+			extension: extension_data,
+		})
+	}
+
+	/// Write the extension into a network buffer.
+	/// The buffer will be written Big-Endian.
+	///
+	/// Returns the number of bytes written.
+	///
+	/// # Errors
+	///
+	/// If the buffer is too small to hold the extension this method will
+	/// return an Error.
+	pub fn to_buf(&self, out: &mut [u8]) -> Result<usize, RtpError> {
+		let extension_len = 4 + self.extension.len() * 4;
+		if out.len() < extension_len {
+			return Err(RtpError::BufferTooSmall { needed: extension_len, got: out.len() });
+		}
+
+		NetworkEndian::write_u16(&mut out[0..], self.extension_id);
+		NetworkEndian::write_u16(&mut out[2..], self.ehl);
+		for (i, block) in self.extension.iter().enumerate() {
+			NetworkEndian::write_u32(&mut out[4 + i * 4..], *block);
+		}
+
+		Ok(extension_len)
+	}
+
+	/// Return the extension id.
+	pub fn extension_id(&self) -> u16 {
+		self.extension_id
+	}
+
+	/// Returns the profile of the extension, given by its extension id.
+	pub fn profile(&self) -> ExtensionProfile {
+		ExtensionProfile::from_id(self.extension_id)
+	}
+
+	/// Return the extension header length. This is the number
+	/// of elements in the extension data (blocks of 32 bits).
+	pub fn extension_header_length(&self) -> u16 {
+		self.ehl
+	}
+
+	/// Get the header extension. This is a `Vec` of `u32`.
+	pub fn extension(&self) -> &Vec<u32> {
+		&self.extension
+	}
+
+	/// Replaces the 32bit chunk of extension data at `idx`.
+	///
+	/// # Errors
+	///
+	/// If `idx` is out of range this method will return an Error.
+	pub fn set_block(&mut self, idx: usize, value: u32) -> Result<(), RtpError> {
+		if idx >= self.extension.len() {
+			return Err(RtpError::HeaderError("Header extension block index is out of range."));
+		}
+
+		self.extension[idx] = value;
+		Ok(())
+	}
+
+	/// Appends a 32bit chunk of extension data, updating the extension
+	/// header length.
+	///
+	/// # Errors
+	///
+	/// If the extension already contains 65535 chunks this method will
+	/// return an Error.
+	pub fn push_block(&mut self, value: u32) -> Result<(), RtpError> {
+		if self.extension.len() >= u16::MAX as usize {
+			return Err(RtpError::HeaderError("Header extension contains too many blocks."));
+		}
+
+		self.extension.push(value);
+		self.ehl = self.extension.len() as u16;
+		Ok(())
+	}
+
+	/// Returns an iterator over the 32bit chunks of extension data with
+	/// their index, for profiles which give each chunk a meaning by its
+	/// position.
+	pub fn blocks(&self) -> impl Iterator<Item = (usize, u32)> + '_ {
+		self.extension.iter().cloned().enumerate()
+	}
+
+	/// Returns the extension data as bytes in on-wire (network) order,
+	/// excluding the extension id and length.
+	///
+	/// Unlike the 32bit chunks this keeps the byte layout used by the
+	/// [RFC-5285](https://tools.ietf.org/html/rfc5285) elements.
+	pub fn raw_bytes(&self) -> Vec<u8> {
+		let mut raw = vec![0u8; self.extension.len() * 4];
+		for (i, block) in self.extension.iter().enumerate() {
+			NetworkEndian::write_u32(&mut raw[i * 4..], *block);
+		}
+		raw
+	}
+
+	/// Returns each 32bit chunk of extension data as its two 16 bit
+	/// halves, most significant (first on the wire) first.
+	pub fn as_u16_pairs(&self) -> Vec<(u16, u16)> {
+		self.extension.iter().map(|&block| ((block >> 16) as u16, block as u16)).collect()
+	}
+
+	/// Parse the extension data into [RFC-5285](https://tools.ietf.org/html/rfc5285)
+	/// elements, using the one-byte or two-byte profile given by the extension id.
+	///
+	/// # Errors
+	///
+	/// If the extension id is not a known profile or the elements do not
+	/// fit in the extension data this method will return an Error.
+	pub fn elements(&self) -> Result<Vec<ExtensionElement>, RtpError> {
+		extension::parse_elements(self.extension_id, &self.raw_bytes())
+	}
+}
+
+impl AsRef<[u32]> for HeaderExtension {
+	fn as_ref(&self) -> &[u32] {
+		&self.extension
+	}
+}
+
+/// The fields of the header extension used for serialization.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct HeaderExtensionFields {
+	extension_id: u16,
+	ehl: u16,
+	extension: Vec<u32>,
+}
+
+#[cfg(feature = "serde")]
+impl From<HeaderExtension> for HeaderExtensionFields {
+	fn from(extension: HeaderExtension) -> Self {
+		HeaderExtensionFields {
+			extension_id: extension.extension_id,
+			ehl: extension.ehl,
+			extension: extension.extension,
+		}
+	}
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<HeaderExtensionFields> for HeaderExtension {
+	type Error = RtpError;
+
+	fn try_from(fields: HeaderExtensionFields) -> Result<Self, RtpError> {
+		if fields.ehl as usize != fields.extension.len() {
+			return Err(RtpError::HeaderError("Header extension length does not match the extension data."));
+		}
+
+		HeaderExtension::new(fields.extension_id, fields.extension)
+	}
+}
+
+/// A borrowed view of an RTP header.
+///
+/// Unlike `Header` the view does not allocate. It keeps slices into the
+/// network buffer and decodes the CSRC identifiers and the extension
+/// lazily.
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderView<'a> {
+	fixed: &'a [u8],
+	csrc_identifiers: &'a [u8],
+	extension: Option<HeaderExtensionView<'a>>,
+}
+
+impl<'a> HeaderView<'a> {
+	/// Construct the view from a network buffer.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// this method will return an Error.
+	pub fn from_buf(header_buf: &'a [u8]) -> Result<Self, RtpError> {
+		if header_buf.len() < 12 {
+			return Err(RtpError::BufferTooSmall { needed: 12, got: header_buf.len() });
+		}
+		let (fixed, header_buf) = header_buf.split_at(12);
+		let info = HeaderInfo(NetworkEndian::read_u16(fixed));
+
+		let csrc_len = info.csrc_count() as usize * 4;
+		if header_buf.len() < csrc_len {
+			return Err(RtpError::TruncatedCsrc {
+				declared: info.csrc_count(),
+				available_words: header_buf.len() / 4,
+				offset: 12,
+			});
+		}
+		let (csrc_identifiers, header_buf) = header_buf.split_at(csrc_len);
+
+		let extension = if info.has_extension() {
+			Some(HeaderExtensionView::from_buf(header_buf).map_err(|e| e.shift_offset(12 + csrc_len))?)
+		} else {
+			None
+		};
+
+		Ok(HeaderView {
+			fixed,
+			csrc_identifiers,
+			extension,
+		})
+	}
+
+	/// Return the header info.
+	pub fn info(&self) -> HeaderInfo {
+		HeaderInfo(NetworkEndian::read_u16(self.fixed))
+	}
+
+	/// Returns the sequence.
+	pub fn sequence(&self) -> u16 {
+		NetworkEndian::read_u16(&self.fixed[2..])
+	}
+
+	/// Returns the timestamp as a `u32`.
+	pub fn timestamp(&self) -> u32 {
+		NetworkEndian::read_u32(&self.fixed[4..])
+	}
+
+	/// Returns the SSRC identifier.
+	pub fn ssrc_identifier(&self) -> u32 {
+		NetworkEndian::read_u32(&self.fixed[8..])
+	}
+
+	/// Returns an iterator over the CSRC identifiers.
+	pub fn csrc_identifiers(&self) -> impl Iterator<Item = u32> + 'a {
+		self.csrc_identifiers.chunks(4).map(NetworkEndian::read_u32)
+	}
+
+	/// Return the header extension.
+	pub fn extension(&self) -> Option<HeaderExtensionView<'a>> {
+		self.extension
+	}
+
+	/// Returns the size of the header on the wire in bytes.
+	pub fn byte_len(&self) -> usize {
+		let extension_len = match self.extension {
+			Some(ref ext) => 4 + ext.extension.len(),
+			None => 0,
+		};
+
+		12 + self.csrc_identifiers.len() + extension_len
+	}
+
+	/// Copies the view into an owned `Header`.
+	pub fn to_header(&self) -> Header {
+		Header {
+			info: self.info(),
+			sequence: self.sequence(),
+			timestamp: self.timestamp(),
+			ssrc_identifier: self.ssrc_identifier(),
+			csrc_identifiers: CSRCIdentifiers::from_buf(self.csrc_identifiers),
+			extension: self.extension.map(|ext| ext.to_extension()),
+		}
+	}
+}
+
+/// A borrowed view of a header extension.
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderExtensionView<'a> {
+	extension_id: u16,
+	extension: &'a [u8],
+}
+
+impl<'a> HeaderExtensionView<'a> {
+	/// Constructs the view from a network buffer.
+	pub fn from_buf(extension_buf: &'a [u8]) -> Result<Self, RtpError> {
+		if extension_buf.len() < 4 {
+			return Err(RtpError::TruncatedExtension { offset: 0 });
+		}
+
+		let id = NetworkEndian::read_u16(extension_buf);
+		let ehl = NetworkEndian::read_u16(&extension_buf[2..]) as usize;
+
+		// Apply the same limit as `HeaderExtension::from_buf`, so that the
+		// views agree with the owned parser on which headers are valid.
+		if ehl > DEFAULT_MAX_EXTENSION_WORDS as usize {
+			return Err(RtpError::HeaderError("Header extension length exceeds the allowed maximum."));
+		}
+
+		if extension_buf.len() < 4 + ehl * 4 {
+			return Err(RtpError::TruncatedExtension { offset: 0 });
+		}
+
+		Ok(HeaderExtensionView {
+			extension_id: id,
+			extension: &extension_buf[4..4 + ehl * 4],
+		})
+	}
+
+	/// Return the extension id.
+	pub fn extension_id(&self) -> u16 {
+		self.extension_id
+	}
+
+	/// Return the extension header length. This is the number
+	/// of elements in the extension data (blocks of 32 bits).
+	pub fn extension_header_length(&self) -> u16 {
+		(self.extension.len() / 4) as u16
+	}
+
+	/// Returns an iterator over the 32bit chunks of extension data.
+	pub fn extension(&self) -> impl Iterator<Item = u32> + 'a {
+		self.extension.chunks(4).map(NetworkEndian::read_u32)
+	}
+
+	/// Copies the view into an owned `HeaderExtension`.
+	pub fn to_extension(&self) -> HeaderExtension {
+		HeaderExtension {
+			extension_id: self.extension_id,
+			ehl: self.extension_header_length(),
+			extension: self.extension().collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+	use super::*;
+	use rtp::packet::Packet;
+	use rtp::random::splitmix64;
+
+	/// Fills a buffer of random length with random bytes, biased towards
+	/// version 2 headers with CSRCs and extensions so that the bounds
+	/// checks are reached rather than failing on the version.
+	fn fuzz_buffer(state: &mut u64) -> Vec<u8> {
+		let len = (splitmix64(state) % 6144) as usize;
+		let mut buf : Vec<u8> = (0..len).map(|_| splitmix64(state) as u8).collect();
+
+		let coin = splitmix64(state);
+		if len > 0 && coin & 0b11 != 0 {
+			buf[0] = 0b10000000 | (buf[0] & 0b00111111);
+		}
+		let offset = 12 + (buf.first().unwrap_or(&0) & 0b1111) as usize * 4 + 2;
+		if offset + 2 <= len && coin & 0b100 == 0 {
+			// Declare an extension length either side of the default limit,
+			// and size the buffer so that it sometimes fits.
+			let ehl = DEFAULT_MAX_EXTENSION_WORDS - 2 + (coin >> 8) as u16 % 5;
+			buf[offset] = (ehl >> 8) as u8;
+			buf[offset + 1] = ehl as u8;
+			if coin & 0b1000 == 0 {
+				buf.resize(offset + 2 + ehl as usize * 4 + (coin >> 16) as usize % 8, 0);
+			}
+		}
+		buf
+	}
+
+	#[test]
+	fn fuzz_from_buf_never_panics() {
+		let mut state = 0x5eed;
+
+		for _ in 0..50_000 {
+			let buf = fuzz_buffer(&mut state);
+
+			let header = Header::from_buf(&buf);
+			let view = HeaderView::from_buf(&buf);
+			assert_eq!(header.is_ok(), view.is_ok());
+
+			if let Ok(header) = header {
+				// A parsed header never claims more bytes than were given.
+				assert!(header.byte_len() <= buf.len());
+				assert_eq!(Header::payload_offset(&buf), Ok(header.byte_len()));
+				assert_eq!(view.unwrap().to_header(), header);
+			}
+
+			let _ = Header::from_buf_lenient(&buf);
+			let _ = Header::from_buf_with_extension_range(&buf);
+			let _ = Header::padding_len(&buf);
+			let _ = Packet::from_buf(&buf);
+		}
+	}
+
+	#[test]
+	fn small_header() {
+		let buf : &[u8]= &[123, 123];
+
+		let header = Header::from_buf(buf);
+
+		assert!(header.is_err())
+	}
+
+	#[test]
+	fn as_ref_slices() {
+		let csrcs = CSRCIdentifiers::from_slice(&[1, 2]).unwrap();
+		let extension = HeaderExtension::new(0xbede, vec![3]).unwrap();
+
+		assert_eq!(csrcs.as_ref(), &[1, 2]);
+		assert_eq!(extension.as_ref(), &[3]);
+	}
+
+	#[test]
+	fn parse_errors() {
+		let buf : &[u8] = &[123, 123];
+		assert_eq!(Header::from_buf(buf), Err(RtpError::BufferTooSmall { needed: 12, got: 2 }));
+
+		let buf : &[u8] = &[0b10000001, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0];
+		assert_eq!(Header::from_buf(buf), Err(RtpError::TruncatedCsrc { declared: 1, available_words: 0, offset: 12 }));
+
+		let buf : &[u8] = &[0b10000011, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2, 0];
+		assert_eq!(Header::from_buf(buf), Err(RtpError::TruncatedCsrc { declared: 3, available_words: 2, offset: 12 }));
+		assert_eq!(HeaderView::from_buf(buf).err(), Some(RtpError::TruncatedCsrc { declared: 3, available_words: 2, offset: 12 }));
+
+		let buf : &[u8] = &[0b10010000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xbe, 0xde];
+		assert_eq!(Header::from_buf(buf), Err(RtpError::TruncatedExtension { offset: 12 }));
+
+		let buf : &[u8] = &[0b10010000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xbe, 0xde, 0, 1];
+		assert_eq!(Header::from_buf(buf), Err(RtpError::TruncatedExtension { offset: 12 }));
+
+		let buf : &[u8] = &[0b01000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		assert_eq!(Header::from_buf_strict(buf), Err(RtpError::UnsupportedVersion(1)));
+	}
+
+	#[test]
+	fn config_expected_extension_length() {
+		let mut buf = vec![
+			0b10010000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1,
+			0xbe, 0xde, 0x00, 0x01,
+			0x10, 0xaa, 0x00, 0x00,
+		];
+		let config = ParserConfig { expected_extension_words: Some(1), ..ParserConfig::default() };
+
+		assert!(Header::from_buf_with_config(&buf, &config).is_ok());
+		let config_two = ParserConfig { expected_extension_words: Some(2), ..ParserConfig::default() };
+		assert!(Header::from_buf_with_config(&buf, &config_two).is_err());
+		assert!(Header::from_buf_with_config(&buf, &ParserConfig::default()).is_ok());
+
+		buf[15] = 0;
+		assert_eq!(
+			Header::from_buf_with_config(&buf, &config),
+			Err(RtpError::HeaderError("Header extension length does not match the expected length."))
+		);
+
+		// Without an extension there is nothing to check.
+		buf[0] = 0b10000000;
+		assert!(Header::from_buf_with_config(&buf, &config).is_ok());
+	}
+
+	#[test]
+	fn to_vec_round_trip() {
+		let header = HeaderBuilder::new()
+			.payload_type(96)
+			.sequence(7)
+			.add_csrc(3)
+			.extension(0xbede, vec![0x10aa0000])
+			.build()
+			.unwrap();
+
+		let bytes = Vec::try_from(&header).unwrap();
+
+		assert_eq!(bytes.len(), header.byte_len());
+		assert_eq!(Header::from_buf(&bytes).unwrap(), header);
+	}
+
+	#[test]
+	fn fixed_header_slices() {
+		let buf : &[u8] = &[
+			0b10010001, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, // X, CC=1
+			0, 0, 0, 2,
+			0xbe, 0xde, 0x00, 0x00,
+		];
+
+		assert_eq!(Header::fixed_bytes(buf), Ok(&buf[..12]));
+		assert_eq!(Header::fixed_and_csrc_bytes(buf), Ok(&buf[..16]));
+
+		assert!(Header::fixed_bytes(&buf[..11]).is_err());
+		assert_eq!(
+			Header::fixed_and_csrc_bytes(&buf[..14]),
+			Err(RtpError::TruncatedCsrc { declared: 1, available_words: 0, offset: 12 })
+		);
+	}
+
+	#[test]
+	fn config_rejects_unflagged_extension() {
+		let mut buf = vec![
+			0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, // X clear
+			0xbe, 0xde, 0x00, 0x01,
+			0x10, 0xaa, 0x00, 0x00,
+		];
+		let strict = ParserConfig { reject_unflagged_extension: true, ..ParserConfig::default() };
+
+		assert!(Header::from_buf_with_config(&buf, &ParserConfig::default()).is_ok());
+		assert_eq!(
+			Header::from_buf_with_config(&buf, &strict),
+			Err(RtpError::HeaderError("Header extension present without the extension flag."))
+		);
+
+		// A length which does not fit is taken to be payload.
+		buf[15] = 2;
+		assert!(Header::from_buf_with_config(&buf, &strict).is_ok());
+
+		// As is an unknown profile.
+		buf[15] = 1;
+		buf[12] = 0x12;
+		assert!(Header::from_buf_with_config(&buf, &strict).is_ok());
+
+		// With the flag set the extension is parsed as usual.
+		buf[0] = 0b10010000;
+		buf[12] = 0xbe;
+		assert!(Header::from_buf_with_config(&buf, &strict).is_ok());
+	}
+
+	#[test]
+	fn from_buf_max_rejects_crafted_lengths() {
+		// 15 CSRCs and the largest extension length, without the data.
+		let mut buf = vec![0b10011111, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		buf.extend_from_slice(&[0; 60]);
+		buf.extend_from_slice(&[0xbe, 0xde, 0xff, 0xff]);
+
+		assert_eq!(
+			Header::from_buf_max(&buf, 128),
+			Err(RtpError::HeaderError("Header length exceeds the allowed maximum."))
+		);
+		assert_eq!(
+			Header::from_buf_max(&buf, 64),
+			Err(RtpError::HeaderError("Packet length exceeds the allowed maximum."))
+		);
+
+		// Within the bound the packet parses as usual.
+		buf[74] = 0;
+		buf[75] = 0;
+		assert!(Header::from_buf_max(&buf, 128).is_ok());
+		assert!(Header::from_buf_max(&buf[..8], 128).is_err());
+	}
+
+	#[test]
+	fn error_offsets() {
+		let buf : &[u8] = &[
+			0b10010010, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, // X, CC=2
+			0, 0, 0, 2,
+			0, 0, 0, 3,
+			0xbe, 0xde, 0x00, 0x01,
+		];
+
+		let err = Header::from_buf(buf).unwrap_err();
+		assert_eq!(err.offset(), Some(20));
+		assert_eq!(HeaderView::from_buf(buf).err().and_then(|e| e.offset()), Some(20));
+		assert_eq!(format!("{}", err), "Truncated header extension at byte 20");
+
+		assert_eq!(Header::from_buf(&buf[..16]).unwrap_err().offset(), Some(12));
+		assert_eq!(Header::from_buf(&buf[..8]).unwrap_err().offset(), None);
+	}
+
+	#[test]
+	fn try_from_slice() {
+		use core::convert::TryInto;
+
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3];
+		let header : Header = buf.try_into().unwrap();
+		assert_eq!(header, Header::from_buf(buf).unwrap());
+
+		let buf : &[u8] = &[123, 123];
+		assert_eq!(Header::try_from(buf), Err(RtpError::BufferTooSmall { needed: 12, got: 2 }));
+	}
+
+	#[test]
+	fn fields_are_big_endian() {
+		let buf : &[u8] = &[
+			0b10010001, 0x60, 0x01, 0x02, // sequence
+			0x01, 0x02, 0x03, 0x04, // timestamp
+			0x05, 0x06, 0x07, 0x08, // ssrc
+			0x09, 0x0a, 0x0b, 0x0c, // csrc
+			0x0d, 0x0e, 0x00, 0x01, // extension id, ehl
+			0x0f, 0x10, 0x11, 0x12, // extension block
+		];
+
+		let header = Header::from_buf(buf).unwrap();
+		assert_eq!(header.sequence(), 0x0102);
+		assert_eq!(header.timestamp(), 0x01020304);
+		assert_eq!(header.ssrc_identifier(), 0x05060708);
+		assert_eq!(header.csrcs().collect::<Vec<_>>(), vec![0x090a0b0c]);
+
+		let ext = header.extension().as_ref().unwrap();
+		assert_eq!(ext.extension_id(), 0x0d0e);
+		assert_eq!(ext.extension_header_length(), 1);
+		assert_eq!(ext.extension(), &vec![0x0f101112]);
+
+		let view = HeaderView::from_buf(buf).unwrap();
+		assert_eq!(view.sequence(), 0x0102);
+		assert_eq!(view.timestamp(), 0x01020304);
+		assert_eq!(view.ssrc_identifier(), 0x05060708);
+
+		let mut out = [0u8; 24];
+		header.to_buf(&mut out).unwrap();
+		assert_eq!(&out[..], buf);
+	}
+
+	#[test]
+	fn strict_rejects_other_versions() {
+		let buf : &[u8] = &[0b01000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+
+		assert!(Header::from_buf(buf).is_ok());
+		assert!(Header::from_buf_strict(buf).is_err());
+
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+
+		assert!(Header::from_buf_strict(buf).is_ok());
+	}
+
+	#[test]
+	fn lenient_salvages_fixed_header() {
+		let buf : &[u8] = &[0b10000010, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4];
+
+		let (header, err) = Header::from_buf_lenient(buf).unwrap();
+
+		assert_eq!(err, Some(RtpError::TruncatedCsrc { declared: 2, available_words: 1, offset: 12 }));
+		assert_eq!(header.sequence(), 1);
+		assert_eq!(header.timestamp(), 2);
+		assert_eq!(header.ssrc_identifier(), 3);
+		assert_eq!(header.csrcs().count(), 0);
+		assert!(header.extension().is_none());
+	}
+
+	#[test]
+	fn validate_header() {
+		let header = HeaderBuilder::new().add_csrc(1).extension(0xbede, vec![1]).build().unwrap();
+		assert_eq!(header.validate(), Ok(()));
+
+		let header = HeaderBuilder::new().version(1).build().unwrap();
+		assert_eq!(header.validate(), Err(RtpError::UnsupportedVersion(1)));
+
+		let buf : &[u8] = &[0b10000010, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4];
+		let (header, _) = Header::from_buf_lenient(buf).unwrap();
+		assert_eq!(
+			header.validate(),
+			Err(RtpError::HeaderError("CSRC count does not match the CSRC identifiers."))
+		);
+
+		let buf : &[u8] = &[0b10010000, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0xbe, 0xde];
+		let (header, _) = Header::from_buf_lenient(buf).unwrap();
+		assert_eq!(
+			header.validate(),
+			Err(RtpError::HeaderError("Extension flag does not match the header extension."))
+		);
+	}
+
+	#[test]
+	fn to_buf_writes_actual_csrc_count() {
+		let buf : &[u8] = &[0b10000010, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4];
+
+		let (header, _) = Header::from_buf_lenient(buf).unwrap();
+		assert_eq!(header.csrc_count(), 2);
+
+		let mut out = [0u8; 12];
+		assert_eq!(header.to_buf(&mut out).unwrap(), 12);
+		assert_eq!(out[0], 0b10000000);
+		assert_eq!(Header::from_buf(&out).unwrap().csrc_count(), 0);
+	}
+
+	#[test]
+	fn lenient_keeps_csrc_on_bad_extension() {
+		let buf : &[u8] = &[
+			0b10010001, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3,
+			0, 0, 0, 4,
+			0xbe, 0xde, 0x00, 0x02,
+			0x01, 0x02, 0x03, 0x04,
+		];
+
+		let (header, err) = Header::from_buf_lenient(buf).unwrap();
+
+		assert_eq!(err, Some(RtpError::TruncatedExtension { offset: 16 }));
+		assert_eq!(header.csrcs().collect::<Vec<_>>(), vec![4]);
+		assert!(header.extension().is_none());
+	}
+
+	#[test]
+	fn lenient_valid_header() {
+		let buf : &[u8] = &[0b10000001, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4];
+
+		let (header, err) = Header::from_buf_lenient(buf).unwrap();
+
+		assert!(err.is_none());
+		assert_eq!(header, Header::from_buf(buf).unwrap());
+		assert!(Header::from_buf_lenient(&buf[..11]).is_err());
+	}
+
+	#[test]
+	fn header_len() {
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa];
+		let (_, len) = Header::from_buf_with_len(buf).unwrap();
+		assert_eq!(len, 12);
+
+		let buf : &[u8] = &[
+			0b10010001, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xbe, 0xde, 0x00, 0x02,
+			0x01, 0x02, 0x03, 0x04,
+			0x05, 0x06, 0x07, 0x08,
+			0xaa, 0xbb,
+		];
+		let (_, len) = Header::from_buf_with_len(buf).unwrap();
+		assert_eq!(len, 28);
+	}
+
+	#[test]
+	fn extension_length_limit() {
+		let ehl = DEFAULT_MAX_EXTENSION_WORDS + 1;
+		let mut buf = vec![0b10010000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xbe, 0xde, (ehl >> 8) as u8, ehl as u8];
+		buf.resize(16 + ehl as usize * 4, 0);
+
+		assert!(Header::from_buf(&buf).is_err());
+		assert!(HeaderView::from_buf(&buf).is_err());
+		assert!(HeaderExtension::from_buf(&buf[12..]).is_err());
+		assert!(HeaderExtensionView::from_buf(&buf[12..]).is_err());
+		assert!(Header::payload_offset(&buf).is_err());
+
+		let header = Header::from_buf_with_extension_limit(&buf, ehl).unwrap();
+		assert_eq!(header.extension().as_ref().unwrap().extension_header_length(), ehl);
+		assert!(Header::from_buf_with_extension_limit(&buf, ehl - 1).is_err());
+	}
+
+	#[test]
+	fn padding_length() {
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0x02];
+		assert_eq!(Header::padding_len(buf), Ok(None));
+
+		let buf : &[u8] = &[0b10100000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0x00, 0x02];
+		assert_eq!(Header::padding_len(buf), Ok(Some(2)));
+
+		let buf : &[u8] = &[0b10100000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0x00];
+		assert_eq!(Header::padding_len(buf), Err(RtpError::InvalidPadding));
+
+		let buf : &[u8] = &[0b10100000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0x03];
+		assert_eq!(Header::padding_len(buf), Err(RtpError::InvalidPadding));
+
+		let buf : &[u8] = &[0b10100000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		assert_eq!(Header::padding_len(buf), Err(RtpError::InvalidPadding));
+	}
+
+	#[test]
+	fn valid_length_ignores_stale_bytes() {
+		let mut buf = [0u8; 64];
+		buf[..16].copy_from_slice(&[0b10000001, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2]);
+
+		assert!(Header::from_buf_len(&buf, 16).is_ok());
+		assert_eq!(
+			Header::from_buf_len(&buf, 14),
+			Err(RtpError::TruncatedCsrc { declared: 1, available_words: 0, offset: 12 })
+		);
+		assert_eq!(Header::from_buf_len(&buf, 65), Err(RtpError::BufferTooSmall { needed: 65, got: 64 }));
+
+		// Stale bytes past the valid length would otherwise be read as CSRCs.
+		buf[0] = 0b10000010;
+		assert!(Header::from_buf(&buf).is_ok());
+		assert!(Header::from_buf_len(&buf, 16).is_err());
+	}
+
+	#[test]
+	fn header_length_bound() {
+		let buf : &[u8] = &[
+			0b10010001, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xbe, 0xde, 0x00, 0x01,
+			0x01, 0x02, 0x03, 0x04,
+		];
+
+		assert!(Header::from_buf_bounded(buf, 24).is_ok());
+		assert_eq!(
+			Header::from_buf_bounded(buf, 23),
+			Err(RtpError::HeaderError("Header length exceeds the allowed maximum."))
+		);
+		assert!(Header::from_buf_bounded(&buf[..12], 12).is_err());
+
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		assert!(Header::from_buf_bounded(buf, 12).is_ok());
+		assert!(Header::from_buf_bounded(buf, 11).is_err());
+	}
+
+	#[test]
+	fn extension_from_buf() {
+		let buf : &[u8] = &[
+			0xbe, 0xde, 0x00, 0x02, // extension id, ehl
+			0xde, 0xad, 0xbe, 0xef,
+			0x00, 0x00, 0x00, 0x2a,
+		];
+
+		let ext = HeaderExtension::from_buf(buf).unwrap();
+		assert_eq!(ext.extension_id(), 0xbede);
+		assert_eq!(ext.extension_header_length(), 2);
+		assert_eq!(ext.extension(), &vec![0xdeadbeef, 42]);
+
+		// One word short of the declared length.
+		assert_eq!(HeaderExtension::from_buf(&buf[..8]), Err(RtpError::TruncatedExtension { offset: 0 }));
+		assert_eq!(HeaderExtension::from_buf(&buf[..3]), Err(RtpError::TruncatedExtension { offset: 0 }));
+	}
+
+	#[test]
+	fn extension_blocks() {
+		let ext = HeaderExtension::new(0x1234, vec![7, 8, 9]).unwrap();
+
+		assert_eq!(ext.blocks().collect::<Vec<_>>(), vec![(0, 7), (1, 8), (2, 9)]);
+	}
+
+	#[test]
+	fn extension_edit_in_place() {
+		let mut header = HeaderBuilder::new().extension(0xbede, vec![0x10aa0000]).build().unwrap();
+
+		{
+			let ext = header.extension_mut().unwrap();
+			ext.set_block(0, 0x10bb0000).unwrap();
+			ext.push_block(0x20cc0000).unwrap();
+			assert!(ext.set_block(2, 0).is_err());
+		}
+
+		let ext = header.extension().as_ref().unwrap();
+		assert_eq!(ext.extension_header_length(), 2);
+		assert_eq!(ext.raw_bytes(), &[0x10, 0xbb, 0x00, 0x00, 0x20, 0xcc, 0x00, 0x00]);
+		assert_eq!(header.byte_len(), 24);
+
+		let bytes = header.to_vec().unwrap();
+		assert_eq!(Header::from_buf(&bytes).unwrap(), header);
+
+		assert!(HeaderBuilder::new().build().unwrap().extension_mut().is_none());
+	}
+
+	#[test]
+	fn extension_push_limit() {
+		let mut ext = HeaderExtension::new(0x1234, vec![0; u16::MAX as usize]).unwrap();
+
+		assert_eq!(ext.push_block(1), Err(RtpError::HeaderError("Header extension contains too many blocks.")));
+		assert_eq!(ext.extension_header_length(), u16::MAX);
+	}
+
+	#[test]
+	fn extension_round_trip() {
+		let buf : &[u8] = &[
+			0xbe, 0xde, 0x00, 0x02,
+			0x01, 0x02, 0x03, 0x04,
+			0x05, 0x06, 0x07, 0x08,
+		];
+
+		let ext = HeaderExtension::from_buf(buf).unwrap();
+		assert_eq!(ext.raw_bytes(), &buf[4..]);
+		assert_eq!(ext.extension(), &vec![0x01020304, 0x05060708]);
+		assert_eq!(ext.as_u16_pairs(), vec![(0x0102, 0x0304), (0x0506, 0x0708)]);
+
+		let mut out = [0u8; 12];
+		assert_eq!(ext.to_buf(&mut out).unwrap(), 12);
+		assert_eq!(&out[..], buf);
+
+		let mut out = [0u8; 11];
+		assert_eq!(ext.to_buf(&mut out), Err(RtpError::BufferTooSmall { needed: 12, got: 11 }));
+	}
+
+	#[test]
+	fn header_byte_len() {
+		let header = HeaderBuilder::new().build().unwrap();
+		assert_eq!(header.byte_len(), 12);
+
+		let header = HeaderBuilder::new().add_csrc(1).add_csrc(2).add_csrc(3).build().unwrap();
+		assert_eq!(header.byte_len(), 24);
+
+		let header = HeaderBuilder::new().extension(0xbede, vec![]).build().unwrap();
+		assert_eq!(header.byte_len(), 16);
+
+		let header = HeaderBuilder::new()
+			.add_csrc(1)
+			.extension(0xbede, vec![1, 2])
+			.build()
+			.unwrap();
+		assert_eq!(header.byte_len(), 28);
+
+		let buf : &[u8] = &[
+			0b10010001, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xbe, 0xde, 0x00, 0x01,
+			0x01, 0x02, 0x03, 0x04,
+			0xaa, 0xbb,
+		];
+		let (header, len) = Header::from_buf_with_len(buf).unwrap();
+		assert_eq!(header.byte_len(), len);
+		assert_eq!(Header::payload_offset(buf), Ok(len));
+		assert_eq!(HeaderView::from_buf(buf).unwrap().byte_len(), len);
+
+		assert_eq!(Header::payload_offset(&buf[..23]), Err(RtpError::TruncatedExtension { offset: 16 }));
+
+		let (_, range) = Header::from_buf_with_extension_range(buf).unwrap();
+		assert_eq!(range, Some((16, 8)));
+		assert_eq!(&buf[16..24], &[0xbe, 0xde, 0x00, 0x01, 0x01, 0x02, 0x03, 0x04]);
+		let (_, range) = Header::from_buf_with_extension_range(&[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1]).unwrap();
+		assert_eq!(range, None);
+
+		assert_eq!(Header::remaining_after_header(buf), Ok(2));
+		assert_eq!(Header::remaining_after_header(&buf[..24]), Ok(0));
+		assert!(Header::remaining_after_header(&buf[..23]).is_err());
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn header_from_bytes() {
+		let buf = Bytes::from_static(&[0b10000000, 96, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3]);
+
+		let header = Header::from_bytes(&buf).unwrap();
 
+		assert_eq!(header, Header::from_buf(&buf).unwrap());
+	}
 
-use byteorder::{ByteOrder, NetworkEndian};
-use super::RtpError;
+	#[test]
+	fn header_round_trip() {
+		let buf : &[u8] = &[
+			0b10010010, 0b11100000, 0x12, 0x34, // V=2, X, CC=2, M, PT=96, seq
+			0x00, 0x00, 0x10, 0x00, // timestamp
+			0xde, 0xad, 0xbe, 0xef, // ssrc
+			0x00, 0x00, 0x00, 0x01, // csrc 1
+			0x00, 0x00, 0x00, 0x02, // csrc 2
+			0xbe, 0xde, 0x00, 0x01, // extension id, ehl
+			0x01, 0x02, 0x03, 0x04, // extension block
+		];
 
-/// The header for the RTP packet.
-#[derive(Debug)]
-pub struct Header {
-	info: HeaderInfo,
-	sequence: u16,
-	timestamp: u32,
-	ssrc_identifier: u32,
-	csrc_identifiers: CSRCIdentifiers,
-	extension: Option<HeaderExtension>,
-}
+		let header = Header::from_buf(buf).unwrap();
 
-impl Header {
-	/// Construct the header from a network buffer.
-	/// Note the buffer will be Big-Endian.
-	///
-	/// # Errors
-	///
-	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
-	/// this method will return an Error.
-	pub fn from_buf(mut header_buf: &[u8]) -> Result<Self, RtpError> {
-		if header_buf.len() < 12 {
-			return Err(RtpError::HeaderError("Buffer is too small to contain a valid header."));
-		}
-		// Extract the static header parts from 0..96 bits
-		// Get the 16 bits for info
-		let info = HeaderInfo(NetworkEndian::read_u16(header_buf));
-		header_buf = &header_buf[2..];
-		
-		// Get bits for sequence
-		let sequence = NetworkEndian::read_u16(header_buf);
-		header_buf = &header_buf[2..];
+		let mut out = [0u8; 28];
+		let written = header.to_buf(&mut out).unwrap();
 
-		// Timestamp is 32 bits
-		let timestamp = NetworkEndian::read_u32(header_buf);
-		header_buf = &header_buf[4..];
+		assert_eq!(written, buf.len());
+		assert_eq!(&out[..], buf);
+	}
 
-		// SSRCS id is 32 bits
-		let ssrc_id = NetworkEndian::read_u32(header_buf);
-		header_buf = &header_buf[4..];
+	#[test]
+	fn to_buf_too_small() {
+		let buf : &[u8] = &[0b10000001, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		let header = Header::from_buf(buf).unwrap();
 
-		// Get the number of csrc identifiers from the header info
-		let csrc_count = info.csrc_count() as usize;
+		let mut out = [0u8; 15];
+		assert!(header.to_buf(&mut out).is_err());
+	}
 
-		// Check that we have room for the CSRC in buffer
-		if header_buf.len() < csrc_count * 4 {
-			return Err(RtpError::HeaderError("Buffer does not contain the specified number of CSRC identifiers."));
-		}
+	#[test]
+	fn header_info_forwarding() {
+		let buf : &[u8] = &[
+			0b10110001, 0b11100000, 0x00, 0x01, // V=2, P, X, CC=1, M, PT=96
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xbe, 0xde, 0x00, 0x00,
+		];
 
-		// Pull the csrc identifiers from the header
-		let mut csrc_data : Vec<u32> = Vec::with_capacity(csrc_count);
-		for _ in 0..csrc_count {
-			csrc_data.push(NetworkEndian::read_u32(header_buf));
-			header_buf = &header_buf[4..];
-		}
-		let csrc_ids = CSRCIdentifiers { identifiers: csrc_data };
+		let header = Header::from_buf(buf).unwrap();
 
-		// Extract the headers
-		let extension: Option<HeaderExtension>;
-		if info.has_extension() {
-			// We try to build the extension from the buffer - will return
-			// an error if format is invalid.
-			extension = Some(try!(HeaderExtension::from_buf(header_buf)));
-		} else {
-			extension = None;
-		}
+		assert_eq!(header.version(), 2);
+		assert!(header.has_padding());
+		assert!(header.has_extension());
+		assert_eq!(header.csrc_count(), 1);
+		assert!(header.has_marker());
+		assert_eq!(header.payload_type(), 96);
+	}
 
-		Ok(Header {
-			info: info,
-			sequence: sequence,
-			timestamp: timestamp,
-			ssrc_identifier: ssrc_id,
-			csrc_identifiers: csrc_ids,
-			extension: extension,
-		})
+	#[test]
+	fn header_setters() {
+		let mut header = HeaderBuilder::new().build().unwrap();
+
+		header.set_sequence(65535);
+		header.set_timestamp(160);
+		header.set_ssrc_identifier(0xcafe);
+
+		assert_eq!(header.sequence(), 65535);
+		assert_eq!(header.timestamp(), 160);
+		assert_eq!(header.ssrc_identifier(), 0xcafe);
 	}
 
-	/// Return the header info.
-	pub fn info(&self) -> &HeaderInfo {
-		&self.info
+	#[test]
+	fn header_equality() {
+		let buf : &[u8] = &[
+			0b10010001, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xbe, 0xde, 0x00, 0x01,
+			0x01, 0x02, 0x03, 0x04,
+		];
+
+		let header = Header::from_buf(buf).unwrap();
+		let mut other = header.clone();
+		assert_eq!(header, other);
+
+		other.set_sequence(2);
+		assert!(header != other);
 	}
 
-	/// Returns the sequence.
-	pub fn sequence(&self) -> u16 {
-		self.sequence
+	#[test]
+	fn extension_elements() {
+		let ext = HeaderBuilder::new()
+			.extension(0xbede, vec![0x10aa21bb, 0xcc000000])
+			.build()
+			.unwrap()
+			.extension()
+			.clone()
+			.unwrap();
+
+		assert_eq!(ext.raw_bytes(), &[0x10, 0xaa, 0x21, 0xbb, 0xcc, 0, 0, 0]);
+		assert_eq!(ext.profile(), ExtensionProfile::OneByte);
+
+		let elements = ext.elements().unwrap();
+
+		assert_eq!(elements.len(), 2);
+		assert_eq!(elements[0].id(), 1);
+		assert_eq!(elements[0].data(), &[0xaa]);
+		assert_eq!(elements[1].id(), 2);
+		assert_eq!(elements[1].data(), &[0xbb, 0xcc]);
 	}
 
-	/// Returns the timestamp as a `u32`.
-	pub fn timestamp(&self) -> u32 {
-		self.timestamp
+	#[test]
+	fn header_view_matches_header() {
+		let buf : &[u8] = &[
+			0b10010010, 0b11100000, 0x12, 0x34,
+			0x00, 0x00, 0x10, 0x00,
+			0xde, 0xad, 0xbe, 0xef,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xbe, 0xde, 0x00, 0x01,
+			0x01, 0x02, 0x03, 0x04,
+		];
+
+		let view = HeaderView::from_buf(buf).unwrap();
+
+		assert_eq!(view.info().csrc_count(), 2);
+		assert_eq!(view.sequence(), 0x1234);
+		assert_eq!(view.timestamp(), 0x1000);
+		assert_eq!(view.ssrc_identifier(), 0xdeadbeef);
+		assert_eq!(view.csrc_identifiers().collect::<Vec<_>>(), vec![1, 2]);
+
+		let ext = view.extension().unwrap();
+		assert_eq!(ext.extension_id(), 0xbede);
+		assert_eq!(ext.extension_header_length(), 1);
+		assert_eq!(ext.extension().collect::<Vec<_>>(), vec![0x01020304]);
+
+		assert_eq!(view.to_header(), Header::from_buf(buf).unwrap());
 	}
 
-	/// Returns the SSRC identifier.
-	pub fn ssrc_identifier(&self) -> u32 {
-		self.ssrc_identifier
+	#[test]
+	fn header_view_truncated() {
+		let buf : &[u8] = &[0b10000001, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0];
+		assert!(HeaderView::from_buf(buf).is_err());
+
+		let buf : &[u8] = &[0b10010000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		assert!(HeaderView::from_buf(buf).is_err());
 	}
 
-	/// Returns the CSRC identifiers.
-	pub fn csrc_identifiers(&self) -> &Vec<u32> {
-		&self.csrc_identifiers.identifiers
+	#[test]
+	fn csrc_mutation() {
+		let mut header = HeaderBuilder::new().marker(true).payload_type(96).build().unwrap();
+
+		for i in 0..15 {
+			header.push_csrc(i).unwrap();
+		}
+		assert_eq!(header.csrc_count(), 15);
+		assert!(header.push_csrc(15).is_err());
+		assert_eq!(header.csrc_count(), 15);
+
+		assert_eq!(header.remove_csrc(0), Some(0));
+		assert_eq!(header.remove_csrc(14), None);
+		assert_eq!(header.csrc_count(), 14);
+		assert_eq!(header.csrcs().next(), Some(1));
+
+		header.clear_csrc();
+		assert_eq!(header.csrc_count(), 0);
+		assert_eq!(header.csrcs().count(), 0);
+
+		// The other info bits are untouched.
+		assert_eq!(header.version(), 2);
+		assert!(header.has_marker());
+		assert_eq!(header.payload_type(), 96);
 	}
 
-	/// Return the header extension.
-	pub fn extension(&self) -> &Option<HeaderExtension> {
-		&self.extension
+	#[cfg(feature = "serde")]
+	#[test]
+	fn serde_round_trip() {
+		let header = HeaderBuilder::new()
+			.marker(true)
+			.payload_type(96)
+			.sequence(1234)
+			.add_csrc(1)
+			.extension(0xbede, vec![0x01020304])
+			.build()
+			.unwrap();
+
+		let json = ::serde_json::to_value(&header).unwrap();
+		assert_eq!(json["info"]["version"], 2);
+		assert_eq!(json["info"]["marker"], true);
+		assert_eq!(json["info"]["payload_type"], 96);
+		assert_eq!(json["info"]["csrc_count"], 1);
+		assert_eq!(json["csrc_identifiers"], ::serde_json::json!([1]));
+
+		let parsed : Header = ::serde_json::from_value(json).unwrap();
+		assert_eq!(parsed, header);
 	}
-}
 
-/// The header info
-///
-/// These 16 bits contain information for the rest of the header.
-#[derive(Debug)]
-pub struct HeaderInfo(u16);
+	#[cfg(feature = "serde")]
+	#[test]
+	fn serde_rejects_invalid_info() {
+		let json = r#"{"version":2,"padding":false,"extension":false,"csrc_count":0,"marker":false,"payload_type":128}"#;
 
-impl HeaderInfo {
-	/// Gets the version from the header info.
-	pub fn version(&self) -> u8 {
-		(self.0 >> 14) as u8
+		assert!(::serde_json::from_str::<HeaderInfo>(json).is_err());
+
+		let json = ::serde_json::to_value([0u32; 16]).unwrap();
+		assert!(::serde_json::from_value::<CSRCIdentifiers>(json).is_err());
+
+		let json = r#"{"extension_id":48862,"ehl":2,"extension":[1]}"#;
+		assert!(::serde_json::from_str::<HeaderExtension>(json).is_err());
+		let json = r#"{"extension_id":48862,"ehl":1,"extension":[1]}"#;
+		assert!(::serde_json::from_str::<HeaderExtension>(json).is_ok());
 	}
 
-	/// Gets the padding flag from the header info.
-	pub fn has_padding(&self) -> bool {
-		((self.0 >> 13) & 0b1) == 1
+	#[test]
+	fn timestamp_duration() {
+		let first = HeaderBuilder::new().timestamp(8000).build().unwrap();
+		let second = HeaderBuilder::new().timestamp(8160).build().unwrap();
+
+		assert_eq!(second.timestamp_duration_since(&first, 8000), Duration::from_millis(20));
+		assert_eq!(first.timestamp_duration_since(&second, 8000), Duration::from_secs(0));
 	}
 
-	/// Gets the extension flag from the header info.
-	pub fn has_extension(&self) -> bool {
-		((self.0 >> 12) & 0b1) == 1
+	#[test]
+	fn timestamp_duration_across_wrap() {
+		let first = HeaderBuilder::new().timestamp(u32::MAX - 79).build().unwrap();
+		let second = HeaderBuilder::new().timestamp(80).build().unwrap();
+
+		assert_eq!(second.timestamp_duration_since(&first, 8000), Duration::from_millis(20));
+		assert_eq!(first.timestamp_duration_since(&second, 8000), Duration::from_secs(0));
 	}
 
-	/// Gets the number of CSRC identifiers in the header.
-	pub fn csrc_count(&self) -> u8 {
-		((self.0 >> 8) & 0b1111) as u8
+	#[test]
+	fn header_summary() {
+		let header = HeaderBuilder::new()
+			.marker(true)
+			.payload_type(96)
+			.sequence(1234)
+			.timestamp(567890)
+			.ssrc(0xabcd12)
+			.add_csrc(1)
+			.build()
+			.unwrap();
+
+		assert_eq!(header.summary(), HeaderSummary {
+			version: 2,
+			payload_type: 96,
+			marker: true,
+			sequence: 1234,
+			timestamp: 567890,
+			ssrc: 0xabcd12,
+			csrc_count: 1,
+			has_extension: false,
+		});
 	}
 
-	/// Gets the marker flag from the header info.
-	pub fn has_marker(&self) -> bool {
-		((self.0 >> 7) & 0b1) == 1
+	#[test]
+	fn header_display() {
+		let header = HeaderBuilder::new()
+			.payload_type(96)
+			.sequence(1234)
+			.timestamp(567890)
+			.ssrc(0xabcd12)
+			.build()
+			.unwrap();
+
+		assert_eq!(format!("{}", header), "RTP v2 PT=96 M=0 seq=1234 ts=567890 ssrc=0xABCD12 csrc=[] ext=no");
+
+		let header = HeaderBuilder::new()
+			.marker(true)
+			.payload_type(0)
+			.ssrc(1)
+			.add_csrc(1)
+			.add_csrc(0xff)
+			.extension(0xbede, vec![])
+			.build()
+			.unwrap();
+
+		assert_eq!(format!("{}", header), "RTP v2 PT=0 M=1 seq=0 ts=0 ssrc=0x1 csrc=[0x1, 0xFF] ext=yes");
 	}
 
-	/// Gets the payload type of the packet
-	pub fn payload_type(&self) -> u8 {
-		(self.0 & 0b1111111) as u8
+	#[test]
+	fn new_header() {
+		let header = Header::new(96, 1234, 567890, 0xdeadbeef);
+
+		assert_eq!(header.version(), 2);
+		assert_eq!(header.payload_type(), 96);
+		assert_eq!(header.sequence(), 1234);
+		assert_eq!(header.timestamp(), 567890);
+		assert_eq!(header.ssrc_identifier(), 0xdeadbeef);
+		assert!(!header.has_marker());
+		assert_eq!(header.csrc_count(), 0);
+		assert!(header.extension().is_none());
+
+		let built = HeaderBuilder::new()
+			.payload_type(96)
+			.sequence(1234)
+			.timestamp(567890)
+			.ssrc(0xdeadbeef)
+			.build()
+			.unwrap();
+		assert_eq!(header, built);
 	}
-}
 
-/// The CSRC identifiers
-///
-/// These are the contributing source IDs for when stream has been
-/// generated from multiple sources.
-#[derive(Debug)]
-pub struct CSRCIdentifiers {
-	identifiers: Vec<u32>
-}
+	#[cfg(feature = "std")]
+	#[test]
+	fn hash_and_dedup_key() {
+		use std::collections::HashSet;
 
-/// The header extension.
-///
-/// This contains the extension id, the extension length, and the 32bit chunks
-/// of extension data.
-#[derive(Debug)]
-pub struct HeaderExtension {
-	extension_id: u16,
-	ehl: u16,
-	extension: Vec<u32>,
-}
+		let a = Header::new(96, 1, 100, 0xabcd);
+		let b = Header::new(96, 1, 100, 0xabcd);
+		let c = Header::new(96, 2, 100, 0xabcd);
 
-impl HeaderExtension {
-	/// Constructs a HeaderExtension from a network buffer.
-	pub fn from_buf(mut extension_buf: &[u8]) -> Result<Self, RtpError> {
-		if extension_buf.len() < 4 {
-			return Err(RtpError::HeaderError("Header extension does not contain required info."));
-		}
+		let mut seen = HashSet::new();
+		assert!(seen.insert(a.clone()));
+		assert!(!seen.insert(b.clone()));
+		assert!(seen.insert(c.clone()));
 
-		let id = NetworkEndian::read_u16(extension_buf);
-		extension_buf = &extension_buf[2..];
-		let ehl = NetworkEndian::read_u16(extension_buf);
-		extension_buf = &extension_buf[2..];
+		assert_eq!(a.dedup_key(), (0xabcd, 1));
+		assert_eq!(a.dedup_key(), b.dedup_key());
+		assert_ne!(a.dedup_key(), c.dedup_key());
+	}
 
-		if extension_buf.len() < ehl as usize * 4 {
-			return Err(RtpError::HeaderError("Header extension does not contain specified number of blocks."))
+	#[test]
+	fn max_csrc_count() {
+		let mut buf = vec![0b10001111, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		for i in 0..15u32 {
+			buf.extend_from_slice(&[0xc0, 0, 0, i as u8]);
 		}
+		buf.extend_from_slice(&[0xaa, 0xbb]);
 
-		let mut extension_data : Vec<u32> = Vec::with_capacity(ehl as usize);
-		for _ in 0..ehl as usize {
-			extension_data.push(NetworkEndian::read_u32(extension_buf));
-			extension_buf = &extension_buf[4..];
-		}
+		let (header, len) = Header::from_buf_with_len(&buf).unwrap();
 
-		Ok(HeaderExtension {
-			extension_id: id,
-			ehl: ehl,
-			extension: extension_data,
-		})
+		assert_eq!(header.csrc_count(), 15);
+		let expected : Vec<u32> = (0..15).map(|i| 0xc000_0000 | i).collect();
+		assert_eq!(header.csrcs().collect::<Vec<_>>(), expected);
+		assert_eq!(len, 12 + 60);
+		assert_eq!(Header::payload_offset(&buf), Ok(12 + 60));
+		assert_eq!(header.validate(), Ok(()));
 	}
 
-	/// Return the extension id.
-	pub fn extension_id(&self) -> u16 {
-		self.extension_id
+	#[test]
+	fn csrc_identifiers_empty_and_present() {
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		let header = Header::from_buf(buf).unwrap();
+		assert!(header.csrcs().next().is_none());
+
+		let buf : &[u8] = &[0b10000001, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2];
+		let header = Header::from_buf(buf).unwrap();
+		assert_eq!(header.csrcs().collect::<Vec<_>>(), vec![2]);
 	}
 
-	/// Return the extension header length. This is the number
-	/// of elements in the extension data (blocks of 32 bits).
-	pub fn extension_header_length(&self) -> u16 {
-		self.ehl
+	#[test]
+	fn clone_with_fields() {
+		let header = HeaderBuilder::new().sequence(1).timestamp(2).add_csrc(3).build().unwrap();
+
+		let copy = header.clone_with_sequence(10);
+		assert_eq!(copy.sequence(), 10);
+		assert_eq!(copy.timestamp(), 2);
+		assert_eq!(copy.csrcs().collect::<Vec<_>>(), vec![3]);
+
+		let copy = header.clone_with_timestamp(20);
+		assert_eq!(copy.sequence(), 1);
+		assert_eq!(copy.timestamp(), 20);
+		assert_eq!(header.sequence(), 1);
 	}
 
-	/// Get the header extension. This is a `Vec` of `u32`.
-	pub fn extension(&self) -> &Vec<u32> {
-		&self.extension
+	#[test]
+	fn encode_header_matches_to_buf() {
+		let mut header = Header::new(96, 1234, 567890, 0xdeadbeef);
+		header.set_marker(true);
+
+		let mut expected = [0u8; 12];
+		header.to_buf(&mut expected).unwrap();
+
+		let mut out = [0u8; 16];
+		assert_eq!(encode_header(&mut out, 96, 1234, 567890, 0xdeadbeef, true), Ok(12));
+		assert_eq!(&out[..12], &expected);
+
+		let mut out = [0u8; 11];
+		assert_eq!(
+			encode_header(&mut out, 96, 1234, 567890, 0xdeadbeef, true),
+			Err(RtpError::BufferTooSmall { needed: 12, got: 11 })
+		);
 	}
-}
 
-#[cfg(test)]
-mod tests {
-	use super::*;
+	#[test]
+	fn new_extension() {
+		let ext = HeaderExtension::new(0xbede, vec![0x01020304, 0x05060708]).unwrap();
+		assert_eq!(ext.extension_header_length(), 2);
+		assert_eq!(ext.raw_bytes(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+		let too_long = vec![0u32; u16::MAX as usize + 1];
+		let err = RtpError::HeaderError("Header extension contains too many blocks.");
+		assert_eq!(HeaderExtension::new(0xbede, too_long.clone()), Err(err.clone()));
+		assert_eq!(HeaderBuilder::new().extension(0xbede, too_long).build(), Err(err));
+	}
 
 	#[test]
-	fn small_header() {
-		let buf : &[u8]= &[123, 123];
+	fn builder_defaults() {
+		let header = HeaderBuilder::new().build().unwrap();
 
-		let header = Header::from_buf(buf);
+		assert_eq!(header.info().version(), 2);
+		assert!(!header.info().has_padding());
+		assert!(!header.info().has_extension());
+		assert!(!header.info().has_marker());
+		assert_eq!(header.info().csrc_count(), 0);
+		assert!(header.extension().is_none());
+	}
 
-		assert!(header.is_err())
+	#[test]
+	fn builder_fields() {
+		let header = HeaderBuilder::new()
+			.padding(true)
+			.marker(true)
+			.payload_type(96)
+			.sequence(1234)
+			.timestamp(567890)
+			.ssrc(0xdeadbeef)
+			.add_csrc(1)
+			.add_csrc(2)
+			.extension(0xbede, vec![0x01020304])
+			.build()
+			.unwrap();
+
+		assert!(header.info().has_padding());
+		assert!(header.info().has_marker());
+		assert!(header.info().has_extension());
+		assert_eq!(header.info().payload_type(), 96);
+		assert_eq!(header.info().csrc_count(), 2);
+		assert_eq!(header.sequence(), 1234);
+		assert_eq!(header.timestamp(), 567890);
+		assert_eq!(header.ssrc_identifier(), 0xdeadbeef);
+		assert_eq!(header.csrcs().collect::<Vec<_>>(), vec![1, 2]);
+
+		let ext = header.extension().as_ref().unwrap();
+		assert_eq!(ext.extension_id(), 0xbede);
+		assert_eq!(ext.extension_header_length(), 1);
+		assert_eq!(ext.extension(), &vec![0x01020304]);
+	}
+
+	#[test]
+	fn builder_too_many_csrc() {
+		let mut builder = HeaderBuilder::new();
+		for i in 0..16 {
+			builder = builder.add_csrc(i);
+		}
+
+		assert!(builder.build().is_err());
+	}
+
+	#[test]
+	fn builder_invalid_payload_type() {
+		let header = HeaderBuilder::new().payload_type(128).build();
+
+		assert!(header.is_err());
+	}
+
+	#[test]
+	fn test_header_info_raw() {
+		for &x in &[0u16, 0x8060, 0b1011111111111111, u16::MAX] {
+			assert_eq!(HeaderInfo::from_raw(x).raw(), x);
+		}
+	}
+
+	#[test]
+	fn test_header_info_default() {
+		let info = HeaderInfo::default();
+
+		assert_eq!(info.version(), 2);
+		assert!(!info.has_padding());
+		assert!(!info.has_extension());
+		assert_eq!(info.csrc_count(), 0);
+		assert!(!info.has_marker());
+		assert_eq!(info.payload_type(), 0);
+		assert_eq!(info.raw(), 0x8000);
 	}
 
 	#[test]
@@ -302,4 +2834,84 @@ mod tests {
 		assert_eq!(a.payload_type(), 127);
 	}
 
+	#[test]
+	fn test_header_info_setters() {
+		let mut a = HeaderInfo(0);
+
+		a.set_version(2);
+		a.set_padding(true);
+		a.set_extension(true);
+		a.set_csrc_count(15);
+		a.set_marker(true);
+		a.set_payload_type(96);
+
+		assert_eq!(a.version(), 2);
+		assert!(a.has_padding());
+		assert!(a.has_extension());
+		assert_eq!(a.csrc_count(), 15);
+		assert!(a.has_marker());
+		assert_eq!(a.payload_type(), 96);
+
+		a.set_padding(false);
+		a.set_marker(false);
+		assert!(!a.has_padding());
+		assert!(!a.has_marker());
+		assert_eq!(a.raw(), 0b1001111101100000);
+	}
+
+	#[test]
+	fn test_header_info_setters_mask_input() {
+		let mut a = HeaderInfo(0);
+
+		a.set_payload_type(0xff);
+		assert_eq!(a.raw(), 0b1111111);
+
+		a.set_csrc_count(0xff);
+		assert_eq!(a.csrc_count(), 15);
+		assert!(!a.has_extension());
+
+		a.set_version(0xff);
+		assert_eq!(a.version(), 3);
+		assert!(!a.has_padding());
+	}
+
+	#[test]
+	fn test_header_info_pt_byte() {
+		let mut a = HeaderInfo::default();
+		a.set_marker(true);
+		a.set_payload_type(96);
+		assert_eq!(a.pt_byte(), 0x80 | 96);
+
+		a.set_pt_byte(8);
+		assert!(!a.has_marker());
+		assert_eq!(a.payload_type(), 8);
+		assert_eq!(a.version(), 2);
+
+		a.set_pt_byte(0xff);
+		assert!(a.has_marker());
+		assert_eq!(a.payload_type(), 127);
+		assert_eq!(a.raw(), 0x80ff);
+	}
+
+	#[test]
+	fn test_header_info_payload_type_kind() {
+		let a = HeaderInfo(0b10000000 | 8);
+		assert_eq!(a.payload_type_kind(), PayloadType::Pcma);
+
+		let a = HeaderInfo(111);
+		assert_eq!(a.payload_type_kind(), PayloadType::Dynamic(111));
+	}
+
+	#[test]
+	fn test_header_info_telephony_payloads() {
+		let a = HeaderInfo(0b10000000 | 13);
+		assert!(a.is_comfort_noise());
+		assert!(!a.is_telephone_event(101));
+
+		let a = HeaderInfo(0b10000000 | 101);
+		assert!(!a.is_comfort_noise());
+		assert!(a.is_telephone_event(101));
+		assert!(!a.is_telephone_event(100));
+	}
+
 }
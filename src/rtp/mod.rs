@@ -1,27 +1,130 @@
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
+use core::fmt;
 
+#[cfg(feature = "tokio-codec")]
+pub mod codec;
+pub mod extension;
+pub mod frame;
 pub mod header;
+pub mod packet;
+pub mod payload_type;
+mod random;
+pub mod sequence;
+pub mod source;
+pub mod srtp;
+pub mod stats;
+pub mod stream;
+pub mod telephone_event;
+pub mod timestamp;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RtpError {
-	HeaderError(&'static str)
+	/// A malformed header.
+	HeaderError(&'static str),
+	/// A malformed RTCP packet.
+	RtcpError(&'static str),
+	/// The buffer is smaller than the structure it should contain.
+	BufferTooSmall { needed: usize, got: usize },
+	/// The buffer does not contain the declared CSRC identifiers.
+	///
+	/// Gives the declared CSRC count, the number of whole 32 bit words
+	/// that were available for them and the byte offset at which they
+	/// begin.
+	TruncatedCsrc { declared: u8, available_words: usize, offset: usize },
+	/// The buffer does not contain the declared header extension.
+	///
+	/// Gives the byte offset at which the extension begins.
+	TruncatedExtension { offset: usize },
+	/// The version is not supported.
+	UnsupportedVersion(u8),
+	/// The padding length is zero or exceeds the packet.
+	InvalidPadding,
 }
 
+impl RtpError {
+	/// Returns the byte offset in the buffer at which parsing failed, if
+	/// the error records one.
+	pub fn offset(&self) -> Option<usize> {
+		match *self {
+			RtpError::TruncatedCsrc { offset, .. } => Some(offset),
+			RtpError::TruncatedExtension { offset } => Some(offset),
+			_ => None,
+		}
+	}
+
+	/// Moves the recorded offset by `by` bytes, for errors from parsing a
+	/// structure which starts part way into the buffer.
+	fn shift_offset(self, by: usize) -> Self {
+		match self {
+			RtpError::TruncatedCsrc { declared, available_words, offset } => {
+				RtpError::TruncatedCsrc { declared, available_words, offset: offset + by }
+			}
+			RtpError::TruncatedExtension { offset } => RtpError::TruncatedExtension { offset: offset + by },
+			e => e,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
 impl Error for RtpError {
 	fn description(&self) -> &str {
 		match *self {
-			RtpError::HeaderError(cause) => cause
+			RtpError::HeaderError(cause) => cause,
+			RtpError::RtcpError(cause) => cause,
+			RtpError::BufferTooSmall { .. } => "Buffer is too small.",
+			RtpError::TruncatedCsrc { .. } => "Buffer does not contain the specified number of CSRC identifiers.",
+			RtpError::TruncatedExtension { .. } => "Buffer does not contain the specified header extension.",
+			RtpError::UnsupportedVersion(_) => "Unsupported version.",
+			RtpError::InvalidPadding => "Invalid padding length.",
 		}
 	}
 }
 
+/// Converts the error to an `io::ErrorKind::InvalidData` error wrapping
+/// it, so that parsing composes with `?` in functions returning
+/// `io::Result`.
+#[cfg(feature = "std")]
+impl From<RtpError> for io::Error {
+	fn from(err: RtpError) -> Self {
+		io::Error::new(io::ErrorKind::InvalidData, err)
+	}
+}
+
 impl fmt::Display for RtpError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             // Both underlying errors already impl `Display`, so we defer to
             // their implementations.
             RtpError::HeaderError(cause) => write!(f, "Header Error: {}", cause),
+            RtpError::RtcpError(cause) => write!(f, "RTCP Error: {}", cause),
+            RtpError::BufferTooSmall { needed, got } => {
+                write!(f, "Buffer Too Small: needed {} bytes but got {}", needed, got)
+            }
+            RtpError::TruncatedCsrc { declared, available_words, offset } => {
+                write!(f, "Truncated CSRC identifiers at byte {}: declared {} but only {} present", offset, declared, available_words)
+            }
+            RtpError::TruncatedExtension { offset } => write!(f, "Truncated header extension at byte {}", offset),
+            RtpError::UnsupportedVersion(version) => write!(f, "Unsupported Version: {}", version),
+            RtpError::InvalidPadding => write!(f, "Invalid padding length"),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn into_io_error() {
+		fn parse(buf: &[u8]) -> io::Result<header::Header> {
+			Ok(header::Header::from_buf(buf)?)
+		}
+
+		let err = parse(&[0x80]).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+		assert_eq!(err.to_string(), "Buffer Too Small: needed 12 bytes but got 1");
+	}
+}
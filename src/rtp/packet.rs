@@ -0,0 +1,511 @@
+//! The RTP Packet module.
+//!
+//! This module provides the `Packet` type which bundles an RTP `Header`
+//! together with the payload that follows it on the wire.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use super::RtpError;
+use super::extension::{ExtensionData, ExtensionProfile};
+use super::header::Header;
+use core::ops::Range;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+/// Parses the header from the buffer and locates the payload.
+///
+/// Returns the header and the range of the payload within the buffer,
+/// excluding any padding.
+fn parse(buf: &[u8]) -> Result<(Header, Range<usize>), RtpError> {
+	// The payload begins after the fixed header, the CSRC identifiers
+	// and the extension (if present).
+	let (header, offset) = Header::from_buf_with_len(buf)?;
+	let padding = Header::padding_len(buf)?.unwrap_or(0);
+
+	Ok((header, offset..buf.len() - padding as usize))
+}
+
+/// Parses the header from a `Bytes` buffer, returning it along with the
+/// payload.
+///
+/// The payload shares the allocation of the buffer rather than copying it.
+/// Padding is stripped as in `Packet::from_buf`.
+///
+/// # Errors
+///
+/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+/// or the padding length is invalid this method will return an Error.
+#[cfg(feature = "bytes")]
+pub fn split_bytes(buf: &Bytes) -> Result<(Header, Bytes), RtpError> {
+	let (header, payload) = parse(buf)?;
+	Ok((header, buf.slice(payload)))
+}
+
+/// Copies the packet in `buf` to `out`, replacing its SSRC with
+/// `new_ssrc`, as an SFU does when forwarding.
+///
+/// Only the SSRC (bytes 8..12) differs between the input and the output,
+/// and the packet is not otherwise parsed. Returns the number of bytes
+/// written.
+///
+/// # Errors
+///
+/// If `buf` is too small to contain the fixed header or `out` is smaller
+/// than `buf` this method will return an Error.
+pub fn rewrite_ssrc(buf: &[u8], new_ssrc: u32, out: &mut [u8]) -> Result<usize, RtpError> {
+	if buf.len() < 12 {
+		return Err(RtpError::BufferTooSmall { needed: 12, got: buf.len() });
+	}
+	if out.len() < buf.len() {
+		return Err(RtpError::BufferTooSmall { needed: buf.len(), got: out.len() });
+	}
+
+	out[..buf.len()].copy_from_slice(buf);
+	NetworkEndian::write_u32(&mut out[8..12], new_ssrc);
+
+	Ok(buf.len())
+}
+
+/// Replaces the payload type of the packet in `buf` in place, as a
+/// transcoding gateway does.
+///
+/// Only the low 7 bits of `new_pt` are used. The marker bit and every
+/// other byte are left as they are, and the packet is not otherwise
+/// parsed.
+///
+/// # Errors
+///
+/// If `buf` is smaller than 2 bytes this method will return an Error.
+pub fn rewrite_payload_type(buf: &mut [u8], new_pt: u8) -> Result<(), RtpError> {
+	if buf.len() < 2 {
+		return Err(RtpError::BufferTooSmall { needed: 2, got: buf.len() });
+	}
+
+	buf[1] = (buf[1] & 0x80) | (new_pt & 0x7f);
+	Ok(())
+}
+
+/// An RTP packet.
+///
+/// The packet owns its parsed header and a copy of the payload bytes
+/// which follow the header (and extension) in the network buffer. The
+/// header and payload are stored separately, so they can be borrowed
+/// mutably at the same time with `split_mut`. The number of padding bytes
+/// is kept so the packet can be serialized again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+	header: Header,
+	payload: Vec<u8>,
+	padding: u8,
+}
+
+impl Packet {
+	/// Constructs a packet from a header and payload, without padding.
+	///
+	/// The padding flag of the header is cleared; use `with_padding` to
+	/// pad the packet.
+	pub fn new(mut header: Header, payload: Vec<u8>) -> Self {
+		header.set_padding(false);
+
+		Packet {
+			header,
+			payload,
+			padding: 0,
+		}
+	}
+
+	/// Returns the packet padded with `padding` bytes, including the
+	/// trailing length byte, setting the padding flag of the header if
+	/// `padding` is non-zero and clearing it otherwise.
+	pub fn with_padding(mut self, padding: u8) -> Self {
+		self.header.set_padding(padding > 0);
+		self.padding = padding;
+		self
+	}
+
+	/// Construct the packet from a network buffer.
+	///
+	/// If the padding flag is set the trailing padding bytes are stripped
+	/// from the payload.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// or the padding length is invalid this method will return an Error.
+	pub fn from_buf(buf: &[u8]) -> Result<Self, RtpError> {
+		let (header, payload) = parse(buf)?;
+		let padding = (buf.len() - payload.end) as u8;
+
+		Ok(Packet {
+			header,
+			payload: buf[payload].to_vec(),
+			padding,
+		})
+	}
+
+	/// Serializes the packet into a new network buffer.
+	///
+	/// If the padding flag is set the padding is written after the payload
+	/// as zero bytes, with the padding length in the last byte.
+	///
+	/// # Errors
+	///
+	/// If the padding flag is set but the packet has no padding bytes this
+	/// method will return an Error.
+	pub fn to_vec(&self) -> Result<Vec<u8>, RtpError> {
+		let padding = if self.header.has_padding() {
+			if self.padding == 0 {
+				return Err(RtpError::InvalidPadding);
+			}
+			self.padding as usize
+		} else {
+			0
+		};
+
+		let header_len = self.header.byte_len();
+		let mut buf = vec![0u8; self.wire_len()];
+		self.header.to_buf(&mut buf)?;
+		buf[header_len..header_len + self.payload.len()].copy_from_slice(&self.payload);
+
+		if padding > 0 {
+			let last = buf.len() - 1;
+			buf[last] = padding as u8;
+		}
+
+		Ok(buf)
+	}
+
+	/// Returns the size of the packet on the wire in bytes.
+	///
+	/// This is the header, payload and, if the padding flag is set, the
+	/// padding bytes. It is the length of the buffer `to_vec` returns,
+	/// which fails instead if the padding flag was set through `split_mut`
+	/// without any padding.
+	pub fn wire_len(&self) -> usize {
+		let padding = if self.header.has_padding() { self.padding as usize } else { 0 };
+
+		self.header.byte_len() + self.payload.len() + padding
+	}
+
+	/// Replaces the SSRC of the packet, as an SFU does when forwarding.
+	pub fn rewrite_ssrc(&mut self, new_ssrc: u32) {
+		self.header.set_ssrc_identifier(new_ssrc);
+	}
+
+	/// Borrows the header and payload mutably at the same time, for
+	/// example to rewrite the header while a decoder works on the payload.
+	///
+	/// The payload length cannot be changed through the slice.
+	pub fn split_mut(&mut self) -> (&mut Header, &mut [u8]) {
+		(&mut self.header, &mut self.payload)
+	}
+
+	/// Return the packet header.
+	pub fn header(&self) -> &Header {
+		&self.header
+	}
+
+	/// Return the payload of the packet.
+	pub fn payload(&self) -> &[u8] {
+		&self.payload
+	}
+
+	/// Returns the elements of the header extension.
+	///
+	/// One-byte and two-byte profile extensions are parsed into their
+	/// [RFC-5285](https://tools.ietf.org/html/rfc5285) elements. Any other
+	/// profile is returned as its raw 32bit chunks. A packet without an
+	/// extension has no elements.
+	///
+	/// # Errors
+	///
+	/// If the elements do not fit in the extension data this method will
+	/// return an Error.
+	pub fn extension_elements(&self) -> Result<ExtensionData<'_>, RtpError> {
+		let ext = match *self.header.extension() {
+			Some(ref ext) => ext,
+			None => return Ok(ExtensionData::Elements(Vec::new())),
+		};
+
+		match ext.profile() {
+			ExtensionProfile::OneByte | ExtensionProfile::TwoByte => ext.elements().map(ExtensionData::Elements),
+			ExtensionProfile::Other(_) => Ok(ExtensionData::Raw(ext.as_ref())),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rtp::extension::ExtensionElement;
+	use rtp::header::HeaderBuilder;
+
+	#[test]
+	fn payload_after_fixed_header() {
+		let buf : &[u8] = &[
+			0b10000000, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0xaa, 0xbb, 0xcc,
+		];
+
+		let packet = Packet::from_buf(buf).unwrap();
+
+		assert_eq!(packet.header().sequence(), 1);
+		assert_eq!(packet.payload(), &[0xaa, 0xbb, 0xcc]);
+	}
+
+	#[test]
+	fn payload_after_csrc_and_extension() {
+		let buf : &[u8] = &[
+			0b10010001, 96, 0x00, 0x01, // X, CC=1
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02, // csrc
+			0xbe, 0xde, 0x00, 0x01, // extension id, ehl
+			0x01, 0x02, 0x03, 0x04, // extension block
+			0xaa, 0xbb,
+		];
+
+		let packet = Packet::from_buf(buf).unwrap();
+
+		assert_eq!(packet.payload(), &[0xaa, 0xbb]);
+	}
+
+	#[test]
+	fn extension_elements_by_profile() {
+		let mut buf = vec![
+			0b10010000, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0xbe, 0xde, 0x00, 0x01,
+			0x10, 0xaa, 0x00, 0x00,
+		];
+
+		let packet = Packet::from_buf(&buf).unwrap();
+		let elements = packet.extension_elements().unwrap();
+		assert_eq!(elements, ExtensionData::Elements(vec![ExtensionElement::new(1, vec![0xaa])]));
+
+		buf[12] = 0x12;
+		buf[13] = 0x34;
+		let packet = Packet::from_buf(&buf).unwrap();
+		let elements = packet.extension_elements().unwrap();
+		assert_eq!(elements, ExtensionData::Raw(&[0x10aa0000]));
+
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+		let packet = Packet::from_buf(buf).unwrap();
+		assert_eq!(packet.extension_elements().unwrap(), ExtensionData::Elements(Vec::new()));
+	}
+
+	#[test]
+	fn rewrite_ssrc_changes_only_ssrc() {
+		let buf : &[u8] = &[
+			0b10100001, 96, 0x00, 0x01, // P, CC=1
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xaa, 0xbb, 0x00, 0x02,
+		];
+
+		let mut out = [0u8; 24];
+		assert_eq!(rewrite_ssrc(buf, 0xdeadbeef, &mut out), Ok(buf.len()));
+		assert_eq!(&out[..8], &buf[..8]);
+		assert_eq!(&out[8..12], &[0xde, 0xad, 0xbe, 0xef]);
+		assert_eq!(&out[12..buf.len()], &buf[12..]);
+
+		let mut packet = Packet::from_buf(buf).unwrap();
+		packet.rewrite_ssrc(0xdeadbeef);
+		assert_eq!(packet.to_vec().unwrap(), &out[..buf.len()]);
+
+		assert!(rewrite_ssrc(buf, 1, &mut out[..10]).is_err());
+		assert!(rewrite_ssrc(&buf[..8], 1, &mut out).is_err());
+	}
+
+	#[test]
+	fn rewrite_payload_type_keeps_marker() {
+		let buf : &[u8] = &[0b10000000, 0x80 | 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa];
+
+		let mut out = buf.to_vec();
+		rewrite_payload_type(&mut out, 0).unwrap();
+		assert_eq!(out[1], 0x80);
+		assert_eq!(out[0], buf[0]);
+		assert_eq!(&out[2..], &buf[2..]);
+
+		rewrite_payload_type(&mut out, 0xff).unwrap();
+		assert_eq!(out[1], 0xff);
+
+		out[1] = 96;
+		rewrite_payload_type(&mut out, 8).unwrap();
+		assert_eq!(out[1], 8);
+
+		assert!(rewrite_payload_type(&mut [0x80], 8).is_err());
+	}
+
+	#[test]
+	fn split_mut_borrows_both() {
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0xbb];
+		let mut packet = Packet::from_buf(buf).unwrap();
+
+		{
+			let (header, payload) = packet.split_mut();
+			header.set_ssrc_identifier(2);
+			payload[0] = 0xcc;
+		}
+
+		assert_eq!(packet.header().ssrc_identifier(), 2);
+		assert_eq!(packet.payload(), &[0xcc, 0xbb]);
+	}
+
+	#[test]
+	fn empty_payload() {
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+
+		let packet = Packet::from_buf(buf).unwrap();
+
+		assert!(packet.payload().is_empty());
+	}
+
+	#[test]
+	fn padding_is_stripped() {
+		let buf : &[u8] = &[
+			0b10100000, 96, 0x00, 0x01, // P
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0xaa, 0xbb, 0x00, 0x00, 0x03,
+		];
+
+		let packet = Packet::from_buf(buf).unwrap();
+
+		assert_eq!(packet.payload(), &[0xaa, 0xbb]);
+	}
+
+	#[test]
+	fn to_vec_round_trip() {
+		let buf : &[u8] = &[
+			0b10010001, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xbe, 0xde, 0x00, 0x01,
+			0x01, 0x02, 0x03, 0x04,
+			0xaa, 0xbb,
+		];
+
+		let packet = Packet::from_buf(buf).unwrap();
+		assert_eq!(packet.to_vec().unwrap(), buf);
+		assert_eq!(packet.wire_len(), buf.len());
+	}
+
+	#[test]
+	fn to_vec_zero_fills_padding() {
+		let buf : &[u8] = &[
+			0b10100000, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0xaa, 0xbb, 0x12, 0x34, 0x03,
+		];
+
+		let packet = Packet::from_buf(buf).unwrap();
+		let out = packet.to_vec().unwrap();
+
+		assert_eq!(packet.wire_len(), buf.len());
+		assert_eq!(&out[..14], &buf[..14]);
+		assert_eq!(&out[14..], &[0x00, 0x00, 0x03]);
+		assert_eq!(Packet::from_buf(&out).unwrap(), packet);
+	}
+
+	#[test]
+	fn new_packet_serializes_with_padding() {
+		let header = HeaderBuilder::new().padding(true).sequence(1).build().unwrap();
+
+		let packet = Packet::new(header, vec![0xaa, 0xbb]);
+		assert!(!packet.header().has_padding());
+		assert_eq!(packet.wire_len(), 14);
+		assert_eq!(packet.to_vec().unwrap().len(), 14);
+
+		let packet = packet.with_padding(2);
+		assert!(packet.header().has_padding());
+		let out = packet.to_vec().unwrap();
+		assert_eq!(out.len(), packet.wire_len());
+		assert_eq!(&out[12..], &[0xaa, 0xbb, 0x00, 0x02]);
+		assert_eq!(Packet::from_buf(&out).unwrap(), packet);
+
+		let packet = packet.with_padding(0);
+		assert!(!packet.header().has_padding());
+		assert_eq!(packet.to_vec().unwrap().len(), packet.wire_len());
+	}
+
+	#[test]
+	fn zero_padding_is_invalid() {
+		let buf : &[u8] = &[
+			0b10100000, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0xaa, 0x00,
+		];
+
+		assert_eq!(Packet::from_buf(buf).unwrap_err(), RtpError::InvalidPadding);
+	}
+
+	#[test]
+	fn padding_larger_than_payload_is_invalid() {
+		let buf : &[u8] = &[
+			0b10100000, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0xaa, 0x03,
+		];
+
+		assert_eq!(Packet::from_buf(buf).unwrap_err(), RtpError::InvalidPadding);
+	}
+
+	#[test]
+	fn oversized_padding_never_panics() {
+		let header : &[u8] = &[0b10100000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+
+		for region in 1..8usize {
+			for padding in 0..=255u8 {
+				let mut buf = header.to_vec();
+				buf.resize(header.len() + region, 0xaa);
+				*buf.last_mut().unwrap() = padding;
+
+				let result = Packet::from_buf(&buf);
+				if padding == 0 || padding as usize > region {
+					assert_eq!(result.unwrap_err(), RtpError::InvalidPadding);
+				} else {
+					assert_eq!(result.unwrap().payload().len(), region - padding as usize);
+				}
+			}
+		}
+
+		let buf : &[u8] = &[0b10100000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0xbb, 0xff];
+		assert_eq!(Packet::from_buf(buf).unwrap_err(), RtpError::InvalidPadding);
+		assert_eq!(Header::padding_len(buf), Err(RtpError::InvalidPadding));
+	}
+
+	#[test]
+	fn padding_without_payload_is_invalid() {
+		let buf : &[u8] = &[0b10100000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1];
+
+		assert!(Packet::from_buf(buf).is_err());
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn split_bytes_shares_buffer() {
+		let buf = Bytes::from_static(&[
+			0b10100000, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0xaa, 0xbb, 0x00, 0x02,
+		]);
+
+		let (header, payload) = split_bytes(&buf).unwrap();
+
+		assert_eq!(header.sequence(), 1);
+		assert_eq!(&payload[..], &[0xaa, 0xbb]);
+		assert_eq!(payload.as_ptr(), buf[12..].as_ptr());
+	}
+}
@@ -0,0 +1,325 @@
+//! The RTP payload type module.
+//!
+//! This module maps the static payload types assigned by the RTP/AVP profile
+//! in [RFC-3551](https://tools.ietf.org/html/rfc3551) to a typed enum, and
+//! holds the dynamic payload types negotiated for a session.
+
+use super::RtpError;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/// An RTP payload type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PayloadType {
+	/// ITU-T G.711 mu-law audio (0).
+	Pcmu,
+	/// GSM 06.10 audio (3).
+	Gsm,
+	/// ITU-T G.723.1 audio (4).
+	G723,
+	/// IMA ADPCM audio at 8000 Hz (5).
+	Dvi4_8000,
+	/// IMA ADPCM audio at 16000 Hz (6).
+	Dvi4_16000,
+	/// LPC audio (7).
+	Lpc,
+	/// ITU-T G.711 A-law audio (8).
+	Pcma,
+	/// ITU-T G.722 audio (9).
+	G722,
+	/// Two channel 16 bit linear audio (10).
+	L16Stereo,
+	/// Single channel 16 bit linear audio (11).
+	L16Mono,
+	/// QCELP audio (12).
+	Qcelp,
+	/// Comfort noise (13).
+	Cn,
+	/// MPEG-1 or MPEG-2 audio (14).
+	Mpa,
+	/// ITU-T G.728 audio (15).
+	G728,
+	/// IMA ADPCM audio at 11025 Hz (16).
+	Dvi4_11025,
+	/// IMA ADPCM audio at 22050 Hz (17).
+	Dvi4_22050,
+	/// ITU-T G.729 audio (18).
+	G729,
+	/// Sun CellB video (25).
+	CelB,
+	/// JPEG video (26).
+	Jpeg,
+	/// nv video (28).
+	Nv,
+	/// ITU-T H.261 video (31).
+	H261,
+	/// MPEG-1 or MPEG-2 video (32).
+	Mpv,
+	/// MPEG-2 transport stream (33).
+	Mp2t,
+	/// ITU-T H.263 video (34).
+	H263,
+	/// A dynamically assigned payload type in the range 96-127.
+	Dynamic(u8),
+	/// A reserved or unassigned payload type.
+	Unassigned(u8),
+}
+
+impl PayloadType {
+	/// Constructs the payload type from its number.
+	pub fn from_u8(pt: u8) -> Self {
+		match pt {
+			0 => PayloadType::Pcmu,
+			3 => PayloadType::Gsm,
+			4 => PayloadType::G723,
+			5 => PayloadType::Dvi4_8000,
+			6 => PayloadType::Dvi4_16000,
+			7 => PayloadType::Lpc,
+			8 => PayloadType::Pcma,
+			9 => PayloadType::G722,
+			10 => PayloadType::L16Stereo,
+			11 => PayloadType::L16Mono,
+			12 => PayloadType::Qcelp,
+			13 => PayloadType::Cn,
+			14 => PayloadType::Mpa,
+			15 => PayloadType::G728,
+			16 => PayloadType::Dvi4_11025,
+			17 => PayloadType::Dvi4_22050,
+			18 => PayloadType::G729,
+			25 => PayloadType::CelB,
+			26 => PayloadType::Jpeg,
+			28 => PayloadType::Nv,
+			31 => PayloadType::H261,
+			32 => PayloadType::Mpv,
+			33 => PayloadType::Mp2t,
+			34 => PayloadType::H263,
+			96..=127 => PayloadType::Dynamic(pt),
+			_ => PayloadType::Unassigned(pt),
+		}
+	}
+
+	/// Returns the RTP clock rate of a static payload type in Hz.
+	///
+	/// Dynamic and unassigned payload types have no fixed clock rate
+	/// and return `None`.
+	///
+	/// Note that G.722 samples at 16000 Hz but RFC-3551 fixes its RTP
+	/// clock rate at 8000 Hz for historical reasons.
+	pub fn clock_rate(&self) -> Option<u32> {
+		match *self {
+			PayloadType::Pcmu |
+			PayloadType::Gsm |
+			PayloadType::G723 |
+			PayloadType::Dvi4_8000 |
+			PayloadType::Lpc |
+			PayloadType::Pcma |
+			PayloadType::G722 |
+			PayloadType::Qcelp |
+			PayloadType::Cn |
+			PayloadType::G728 |
+			PayloadType::G729 => Some(8000),
+			PayloadType::Dvi4_16000 => Some(16000),
+			PayloadType::Dvi4_11025 => Some(11025),
+			PayloadType::Dvi4_22050 => Some(22050),
+			PayloadType::L16Stereo |
+			PayloadType::L16Mono => Some(44100),
+			PayloadType::Mpa |
+			PayloadType::CelB |
+			PayloadType::Jpeg |
+			PayloadType::Nv |
+			PayloadType::H261 |
+			PayloadType::Mpv |
+			PayloadType::Mp2t |
+			PayloadType::H263 => Some(90000),
+			PayloadType::Dynamic(_) |
+			PayloadType::Unassigned(_) => None,
+		}
+	}
+}
+
+impl From<u8> for PayloadType {
+	fn from(pt: u8) -> Self {
+		PayloadType::from_u8(pt)
+	}
+}
+
+impl From<PayloadType> for u8 {
+	fn from(pt: PayloadType) -> Self {
+		match pt {
+			PayloadType::Pcmu => 0,
+			PayloadType::Gsm => 3,
+			PayloadType::G723 => 4,
+			PayloadType::Dvi4_8000 => 5,
+			PayloadType::Dvi4_16000 => 6,
+			PayloadType::Lpc => 7,
+			PayloadType::Pcma => 8,
+			PayloadType::G722 => 9,
+			PayloadType::L16Stereo => 10,
+			PayloadType::L16Mono => 11,
+			PayloadType::Qcelp => 12,
+			PayloadType::Cn => 13,
+			PayloadType::Mpa => 14,
+			PayloadType::G728 => 15,
+			PayloadType::Dvi4_11025 => 16,
+			PayloadType::Dvi4_22050 => 17,
+			PayloadType::G729 => 18,
+			PayloadType::CelB => 25,
+			PayloadType::Jpeg => 26,
+			PayloadType::Nv => 28,
+			PayloadType::H261 => 31,
+			PayloadType::Mpv => 32,
+			PayloadType::Mp2t => 33,
+			PayloadType::H263 => 34,
+			PayloadType::Dynamic(pt) |
+			PayloadType::Unassigned(pt) => pt,
+		}
+	}
+}
+
+/// Returns the RTP clock rate in Hz of a static payload type number.
+///
+/// Returns `None` for dynamic and unassigned payload types. See
+/// `PayloadType::clock_rate` for the table.
+pub fn clock_rate_for_static_pt(pt: u8) -> Option<u32> {
+	PayloadType::from_u8(pt).clock_rate()
+}
+
+/// The format registered for a payload type in a `PayloadMap`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadFormat {
+	name: String,
+	clock_rate: u32,
+}
+
+impl PayloadFormat {
+	/// Returns the encoding name, such as `opus` or `H264`.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Returns the RTP clock rate in Hz.
+	pub fn clock_rate(&self) -> u32 {
+		self.clock_rate
+	}
+}
+
+/// A table of the payload types negotiated for a session, typically
+/// populated from the `a=rtpmap` lines of an SDP description.
+#[derive(Debug, Clone, Default)]
+pub struct PayloadMap {
+	formats: BTreeMap<u8, PayloadFormat>,
+}
+
+impl PayloadMap {
+	/// Constructs an empty map.
+	pub fn new() -> Self {
+		PayloadMap::default()
+	}
+
+	/// Registers the format of a payload type, replacing any previous
+	/// registration.
+	///
+	/// # Errors
+	///
+	/// If the payload type does not fit in 7 bits this method will return
+	/// an Error.
+	pub fn register(&mut self, pt: u8, name: &str, clock_rate: u32) -> Result<(), RtpError> {
+		if pt > 0b1111111 {
+			return Err(RtpError::HeaderError("Payload type does not fit in 7 bits."));
+		}
+
+		self.formats.insert(pt, PayloadFormat { name: String::from(name), clock_rate });
+		Ok(())
+	}
+
+	/// Returns the registered format of a payload type.
+	pub fn lookup(&self, pt: u8) -> Option<&PayloadFormat> {
+		self.formats.get(&pt)
+	}
+
+	/// Returns the clock rate of a payload type, from its registration or
+	/// else the static RFC-3551 assignment.
+	pub fn clock_rate(&self, pt: u8) -> Option<u32> {
+		match self.lookup(pt) {
+			Some(format) => Some(format.clock_rate()),
+			None => clock_rate_for_static_pt(pt),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn static_payload_types() {
+		assert_eq!(PayloadType::from_u8(0), PayloadType::Pcmu);
+		assert_eq!(PayloadType::from_u8(8), PayloadType::Pcma);
+		assert_eq!(PayloadType::from_u8(9), PayloadType::G722);
+		assert_eq!(PayloadType::from_u8(34), PayloadType::H263);
+	}
+
+	#[test]
+	fn dynamic_and_unassigned() {
+		assert_eq!(PayloadType::from_u8(96), PayloadType::Dynamic(96));
+		assert_eq!(PayloadType::from_u8(127), PayloadType::Dynamic(127));
+		assert_eq!(PayloadType::from_u8(1), PayloadType::Unassigned(1));
+		assert_eq!(PayloadType::from_u8(72), PayloadType::Unassigned(72));
+	}
+
+	#[test]
+	fn u8_round_trip() {
+		for pt in 0..=255u8 {
+			assert_eq!(u8::from(PayloadType::from(pt)), pt);
+		}
+
+		assert_eq!(u8::from(PayloadType::Mp2t), 33);
+		assert_eq!(PayloadType::from(100), PayloadType::Dynamic(100));
+	}
+
+	#[test]
+	fn clock_rates() {
+		assert_eq!(PayloadType::Pcmu.clock_rate(), Some(8000));
+		assert_eq!(PayloadType::G722.clock_rate(), Some(8000));
+		assert_eq!(PayloadType::Dvi4_16000.clock_rate(), Some(16000));
+		assert_eq!(PayloadType::L16Mono.clock_rate(), Some(44100));
+		assert_eq!(PayloadType::H261.clock_rate(), Some(90000));
+		assert_eq!(PayloadType::Dynamic(96).clock_rate(), None);
+	}
+
+	#[test]
+	fn payload_map() {
+		let mut map = PayloadMap::new();
+		map.register(96, "opus", 48000).unwrap();
+		map.register(97, "H264", 90000).unwrap();
+		assert!(map.register(128, "bad", 8000).is_err());
+
+		let format = map.lookup(96).unwrap();
+		assert_eq!(format.name(), "opus");
+		assert_eq!(format.clock_rate(), 48000);
+		assert!(map.lookup(98).is_none());
+
+		assert_eq!(map.clock_rate(97), Some(90000));
+		assert_eq!(map.clock_rate(0), Some(8000));
+		assert_eq!(map.clock_rate(98), None);
+
+		map.register(96, "VP8", 90000).unwrap();
+		assert_eq!(map.lookup(96).unwrap().name(), "VP8");
+	}
+
+	#[test]
+	fn clock_rate_by_number() {
+		assert_eq!(clock_rate_for_static_pt(0), Some(8000));
+		assert_eq!(clock_rate_for_static_pt(9), Some(8000));
+		assert_eq!(clock_rate_for_static_pt(14), Some(90000));
+		assert_eq!(clock_rate_for_static_pt(26), Some(90000));
+		assert_eq!(clock_rate_for_static_pt(2), None);
+		assert_eq!(clock_rate_for_static_pt(96), None);
+		assert_eq!(clock_rate_for_static_pt(200), None);
+	}
+}
@@ -0,0 +1,29 @@
+//! Random initial values for senders.
+//!
+//! RFC-3550 asks for the initial sequence number and timestamp of a stream
+//! to be random, but they need not be cryptographically strong. This is a
+//! small SplitMix64 generator, seeded from the standard library's hasher
+//! keys when `std` is available.
+
+/// Returns the next value of a SplitMix64 generator.
+pub fn splitmix64(state: &mut u64) -> u64 {
+	*state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+	let mut z = *state;
+	z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	z ^ (z >> 31)
+}
+
+/// Returns a seed which differs between calls and processes.
+#[cfg(feature = "std")]
+pub fn seed() -> u64 {
+	use std::collections::hash_map::RandomState;
+	use std::hash::{BuildHasher, Hasher};
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	let mut hasher = RandomState::new().build_hasher();
+	if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+		hasher.write_u128(now.as_nanos());
+	}
+	hasher.finish()
+}
@@ -0,0 +1,445 @@
+//! The RTP sequence number module.
+//!
+//! RTP sequence numbers are 16 bits and wrap, so they must be compared using
+//! serial number arithmetic per [RFC-1982](https://tools.ietf.org/html/rfc1982)
+//! rather than the natural ordering of `u16`.
+
+use core::cmp::Ordering;
+use core::ops::{Add, Sub};
+use super::random;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+/// Returns the signed distance from `b` to `a`.
+///
+/// The result is positive when `a` is newer than `b` and negative when it
+/// is older, taking the shortest path around the wrap. RFC-1982 leaves the
+/// exact half-way distance of 32768 undefined; here the larger raw value
+/// is treated as the newer so that the result is antisymmetric.
+pub fn seq_diff(a: u16, b: u16) -> i32 {
+	let diff = a.wrapping_sub(b);
+
+	if diff == 0x8000 {
+		if a > b { 0x8000 } else { -0x8000 }
+	} else {
+		diff as i16 as i32
+	}
+}
+
+/// Compares two sequence numbers using serial number arithmetic.
+///
+/// Returns `Ordering::Greater` when `a` is newer than `b`. See `seq_diff`
+/// for the treatment of the half-way point.
+pub fn seq_cmp(a: u16, b: u16) -> Ordering {
+	seq_diff(a, b).cmp(&0)
+}
+
+/// An RTP sequence number.
+///
+/// Arithmetic wraps rather than overflowing. Serial number comparison is
+/// not transitive (30000 is newer than 0, 60000 than 30000 and 0 than
+/// 60000), so the type does not implement `PartialOrd` or `Ord`; use
+/// `is_newer_than` or `diff` to compare, and `ExtendedSequenceNumber` to
+/// sort or to key an ordered collection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SequenceNumber(pub u16);
+
+impl SequenceNumber {
+	/// Returns the following sequence number.
+	pub fn next(self) -> Self {
+		self + 1
+	}
+
+	/// Returns the signed distance from `other` to this sequence number.
+	/// See `seq_diff`.
+	pub fn diff(self, other: SequenceNumber) -> i32 {
+		seq_diff(self.0, other.0)
+	}
+
+	/// Returns true if this sequence number is newer than `other`. See
+	/// `seq_cmp`.
+	pub fn is_newer_than(self, other: SequenceNumber) -> bool {
+		seq_cmp(self.0, other.0) == Ordering::Greater
+	}
+}
+
+impl From<u16> for SequenceNumber {
+	fn from(seq: u16) -> Self {
+		SequenceNumber(seq)
+	}
+}
+
+impl From<SequenceNumber> for u16 {
+	fn from(seq: SequenceNumber) -> Self {
+		seq.0
+	}
+}
+
+impl Add<u16> for SequenceNumber {
+	type Output = SequenceNumber;
+
+	fn add(self, n: u16) -> SequenceNumber {
+		SequenceNumber(self.0.wrapping_add(n))
+	}
+}
+
+impl Sub<u16> for SequenceNumber {
+	type Output = SequenceNumber;
+
+	fn sub(self, n: u16) -> SequenceNumber {
+		SequenceNumber(self.0.wrapping_sub(n))
+	}
+}
+
+impl Sub for SequenceNumber {
+	type Output = u16;
+
+	/// Returns the count from `other` forwards to this sequence number,
+	/// wrapping. Use `diff` for a signed distance.
+	fn sub(self, other: SequenceNumber) -> u16 {
+		self.0.wrapping_sub(other.0)
+	}
+}
+
+/// A sequence number extended with the number of times the 16 bit
+/// sequence number has wrapped, as produced by a `SequenceExtender`.
+///
+/// Unlike `SequenceNumber` the extended sequence numbers of a stream are
+/// totally ordered, so they can be sorted or used as the key of a
+/// `BTreeMap`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExtendedSequenceNumber(pub i64);
+
+impl ExtendedSequenceNumber {
+	/// Returns the 16 bit sequence number.
+	pub fn sequence(self) -> SequenceNumber {
+		SequenceNumber(self.0 as u16)
+	}
+}
+
+/// Extends the 16 bit sequence numbers of a stream with their wrap count.
+///
+/// Each sequence number is placed at the shortest distance from the one
+/// before it, so packets order correctly as long as consecutive packets
+/// are less than half of the sequence space apart. The first sequence
+/// number is extended to itself, and packets reordered before it across
+/// the wrap are extended to negative values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SequenceExtender {
+	last: Option<ExtendedSequenceNumber>,
+}
+
+impl SequenceExtender {
+	/// Constructs an extender which has not yet seen a sequence number.
+	pub fn new() -> Self {
+		SequenceExtender::default()
+	}
+
+	/// Returns the extended sequence number of the next packet.
+	pub fn extend(&mut self, seq: u16) -> ExtendedSequenceNumber {
+		let extended = match self.last {
+			Some(last) => ExtendedSequenceNumber(last.0 + seq_diff(seq, last.0 as u16) as i64),
+			None => ExtendedSequenceNumber(seq as i64),
+		};
+
+		self.last = Some(extended);
+		extended
+	}
+}
+
+/// Generates the sequence numbers of an outgoing stream.
+///
+/// Sequence numbers increase by one for each packet and wrap from 65535
+/// to 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceGenerator {
+	next: u16,
+}
+
+impl SequenceGenerator {
+	/// Constructs a generator starting at the given sequence number.
+	pub fn starting_at(seq: u16) -> Self {
+		SequenceGenerator { next: seq }
+	}
+
+	/// Constructs a generator starting at a random sequence number, as
+	/// recommended by RFC-3550.
+	#[cfg(feature = "std")]
+	pub fn new_random() -> Self {
+		SequenceGenerator::from_seed(random::seed())
+	}
+
+	/// Constructs a generator starting at a sequence number derived from
+	/// the seed, so that tests are reproducible.
+	pub fn from_seed(seed: u64) -> Self {
+		let mut state = seed;
+		SequenceGenerator::starting_at(random::splitmix64(&mut state) as u16)
+	}
+
+	/// Returns the next sequence number.
+	#[allow(clippy::should_implement_trait)]
+	pub fn next(&mut self) -> u16 {
+		let seq = self.next;
+		self.next = seq.wrapping_add(1);
+		seq
+	}
+
+	/// Returns the sequence number `next` will return, without advancing.
+	pub fn peek(&self) -> u16 {
+		self.next
+	}
+}
+
+/// The number of dropped sequence numbers a `SequenceRewriter` remembers
+/// to map late packets.
+const MAX_REMEMBERED_DROPS: usize = 64;
+
+/// Rewrites the sequence numbers of a forwarded stream so that they stay
+/// contiguous when packets are dropped, as an SFU does.
+///
+/// Each dropped packet shifts the output of every newer packet down by
+/// one. A drop which is not newer than the highest packet already
+/// forwarded is ignored, since the outputs around it have been sent. The
+/// newest drops are remembered so that a packet arriving late, after a
+/// newer packet was dropped, is still mapped as it would have been in
+/// order. A packet older than the forgotten drops is assumed to precede
+/// all of them.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceRewriter {
+	/// The highest input sequence number forwarded.
+	highest: Option<u16>,
+	/// The remembered dropped input sequence numbers, oldest first.
+	drops: VecDeque<u16>,
+	/// The newest drop which is no longer remembered.
+	forgotten: Option<u16>,
+	/// The number of drops no longer remembered, modulo 2^16.
+	forgotten_count: u16,
+}
+
+impl SequenceRewriter {
+	/// Constructs a rewriter which has not dropped any packets.
+	pub fn new() -> Self {
+		SequenceRewriter::default()
+	}
+
+	/// Returns the output sequence number for a forwarded packet.
+	pub fn rewrite(&mut self, input_seq: u16) -> u16 {
+		if self.highest.is_none_or(|highest| seq_cmp(input_seq, highest) == Ordering::Greater) {
+			self.highest = Some(input_seq);
+		}
+
+		// Only drops of older packets shift this one.
+		let older_drops = self.drops.iter()
+			.filter(|&&dropped| seq_cmp(dropped, input_seq) == Ordering::Less)
+			.count();
+		let forgotten_drops = match self.forgotten {
+			Some(forgotten) if seq_cmp(input_seq, forgotten) == Ordering::Greater => self.forgotten_count,
+			_ => 0,
+		};
+
+		input_seq.wrapping_sub(forgotten_drops).wrapping_sub(older_drops as u16)
+	}
+
+	/// Records that the packet with the input sequence number is dropped
+	/// rather than forwarded.
+	pub fn drop(&mut self, input_seq: u16) {
+		if self.highest.is_some_and(|highest| seq_cmp(input_seq, highest) != Ordering::Greater) {
+			return;
+		}
+
+		// Keep the drops ordered so the oldest is forgotten first.
+		let pos = self.drops.iter().position(|&dropped| seq_cmp(dropped, input_seq) != Ordering::Less);
+		match pos {
+			Some(i) if self.drops[i] == input_seq => return,
+			Some(i) => self.drops.insert(i, input_seq),
+			None => self.drops.push_back(input_seq),
+		}
+
+		if self.drops.len() > MAX_REMEMBERED_DROPS {
+			self.forgotten = self.drops.pop_front();
+			self.forgotten_count = self.forgotten_count.wrapping_add(1);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::cmp::Ordering;
+
+	#[test]
+	fn diff_without_wrap() {
+		assert_eq!(seq_diff(10, 5), 5);
+		assert_eq!(seq_diff(5, 10), -5);
+		assert_eq!(seq_diff(7, 7), 0);
+	}
+
+	#[test]
+	fn diff_across_wrap() {
+		assert_eq!(seq_diff(0, 65535), 1);
+		assert_eq!(seq_diff(65535, 0), -1);
+		assert_eq!(seq_diff(2, 65533), 5);
+	}
+
+	#[test]
+	fn cmp_across_wrap() {
+		assert_eq!(seq_cmp(0, 65535), Ordering::Greater);
+		assert_eq!(seq_cmp(65535, 0), Ordering::Less);
+		assert_eq!(seq_cmp(100, 100), Ordering::Equal);
+	}
+
+	#[test]
+	fn halfway_is_antisymmetric() {
+		assert_eq!(seq_diff(32768, 0), 32768);
+		assert_eq!(seq_diff(0, 32768), -32768);
+		assert_eq!(seq_cmp(32768, 0), Ordering::Greater);
+		assert_eq!(seq_cmp(0, 32768), Ordering::Less);
+
+		assert_eq!(seq_cmp(40000, 7232), Ordering::Greater);
+		assert_eq!(seq_cmp(7232, 40000), Ordering::Less);
+	}
+
+	#[test]
+	fn sequence_number_arithmetic_wraps() {
+		let seq = SequenceNumber(65535);
+
+		assert_eq!(seq.next(), SequenceNumber(0));
+		assert_eq!(seq + 2, SequenceNumber(1));
+		assert_eq!(SequenceNumber(1) - 2, seq);
+		assert_eq!(SequenceNumber(1) - seq, 2);
+		assert_eq!(seq.diff(SequenceNumber(1)), -2);
+		assert_eq!(u16::from(SequenceNumber::from(9)), 9);
+	}
+
+	#[test]
+	fn sequence_number_compares_across_wrap() {
+		assert!(SequenceNumber(65535).is_newer_than(SequenceNumber(65534)));
+		assert!(SequenceNumber(0).is_newer_than(SequenceNumber(65535)));
+		assert!(!SequenceNumber(65534).is_newer_than(SequenceNumber(2)));
+		assert!(!SequenceNumber(1).is_newer_than(SequenceNumber(1)));
+	}
+
+	#[test]
+	fn extender_counts_wraps() {
+		let mut extender = SequenceExtender::new();
+
+		assert_eq!(extender.extend(65535), ExtendedSequenceNumber(65535));
+		assert_eq!(extender.extend(1), ExtendedSequenceNumber(65537));
+		assert_eq!(extender.extend(0), ExtendedSequenceNumber(65536));
+		assert_eq!(extender.extend(65534), ExtendedSequenceNumber(65534));
+		assert_eq!(extender.extend(1).sequence(), SequenceNumber(1));
+
+		let mut extender = SequenceExtender::new();
+		extender.extend(0);
+		assert_eq!(extender.extend(65535), ExtendedSequenceNumber(-1));
+		assert_eq!(ExtendedSequenceNumber(-1).sequence(), SequenceNumber(65535));
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn extended_sequence_number_orders_map_across_wrap() {
+		use std::collections::BTreeMap;
+
+		let mut extender = SequenceExtender::new();
+		let mut packets = BTreeMap::new();
+		for &seq in &[1u16, 65534, 0, 65535, 2] {
+			packets.insert(extender.extend(seq), seq);
+		}
+
+		let order : Vec<u16> = packets.values().cloned().collect();
+		assert_eq!(order, vec![65534, 65535, 0, 1, 2]);
+	}
+
+	#[test]
+	fn generator_wraps() {
+		let mut gen = SequenceGenerator::starting_at(65534);
+
+		assert_eq!(gen.next(), 65534);
+		assert_eq!(gen.next(), 65535);
+		assert_eq!(gen.peek(), 0);
+		assert_eq!(gen.next(), 0);
+		assert_eq!(gen.next(), 1);
+	}
+
+	#[test]
+	fn generator_seed_is_reproducible() {
+		let mut a = SequenceGenerator::from_seed(42);
+		let mut b = SequenceGenerator::from_seed(42);
+
+		assert_eq!(a.next(), b.next());
+		assert_ne!(SequenceGenerator::from_seed(1), SequenceGenerator::from_seed(2));
+	}
+
+	#[test]
+	fn rewriter_closes_gaps() {
+		let mut rewriter = SequenceRewriter::new();
+
+		assert_eq!(rewriter.rewrite(10), 10);
+		rewriter.drop(11);
+		rewriter.drop(12);
+		assert_eq!(rewriter.rewrite(13), 11);
+		assert_eq!(rewriter.rewrite(14), 12);
+	}
+
+	#[test]
+	fn rewriter_drops_across_wrap() {
+		let mut rewriter = SequenceRewriter::new();
+
+		assert_eq!(rewriter.rewrite(65534), 65534);
+		rewriter.drop(65535);
+		assert_eq!(rewriter.rewrite(0), 65535);
+		rewriter.drop(1);
+		assert_eq!(rewriter.rewrite(2), 0);
+		assert_eq!(rewriter.rewrite(3), 1);
+	}
+
+	#[test]
+	fn rewriter_maps_late_packets() {
+		let mut rewriter = SequenceRewriter::new();
+
+		assert_eq!(rewriter.rewrite(65535), 65535);
+		rewriter.drop(1);
+		// 0 arrives after the newer packet 1 was dropped.
+		assert_eq!(rewriter.rewrite(0), 0);
+		assert_eq!(rewriter.rewrite(2), 1);
+
+		// Dropping the same packet twice only closes one gap.
+		rewriter.drop(1);
+		assert_eq!(rewriter.rewrite(3), 2);
+	}
+
+	#[test]
+	fn rewriter_ignores_drop_of_forwarded_range() {
+		let mut rewriter = SequenceRewriter::new();
+
+		assert_eq!(rewriter.rewrite(10), 10);
+		assert_eq!(rewriter.rewrite(11), 11);
+		rewriter.drop(5);
+		rewriter.drop(11);
+		assert_eq!(rewriter.rewrite(12), 12);
+	}
+
+	#[test]
+	fn rewriter_maps_late_packets_beyond_remembered_drops() {
+		let mut rewriter = SequenceRewriter::new();
+
+		assert_eq!(rewriter.rewrite(65534), 65534);
+		for seq in 0..MAX_REMEMBERED_DROPS as u16 + 2 {
+			rewriter.drop(seq);
+		}
+
+		// 65535 is older than every drop, including the forgotten ones.
+		assert_eq!(rewriter.rewrite(65535), 65535);
+		assert_eq!(rewriter.rewrite(MAX_REMEMBERED_DROPS as u16 + 2), 0);
+		assert_eq!(rewriter.rewrite(MAX_REMEMBERED_DROPS as u16 + 3), 1);
+	}
+
+	#[test]
+	fn just_under_halfway() {
+		assert_eq!(seq_cmp(32767, 0), Ordering::Greater);
+		assert_eq!(seq_cmp(32769, 0), Ordering::Less);
+	}
+}
@@ -0,0 +1,216 @@
+//! The RTP source module.
+//!
+//! This module tracks the synchronization sources seen by a receiver and
+//! implements parts of the source handling of
+//! [RFC-3550](https://tools.ietf.org/html/rfc3550) section 8.
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/// The number of consecutive packets required before a new source is
+/// valid, as suggested by RFC-3550 appendix A.1.
+pub const MIN_SEQUENTIAL: u16 = 2;
+
+/// The result of observing an SSRC in a `SsrcRegistry`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SsrcEvent<A> {
+	/// The SSRC was not seen before and is now registered to the source.
+	New,
+	/// The SSRC was seen before from the same source.
+	Known,
+	/// The SSRC is registered to a different source, given here. The
+	/// registration is unchanged.
+	Collision(A),
+}
+
+/// Records the source address of each SSRC to detect collisions.
+///
+/// The address type is generic, for example a `SocketAddr` or a
+/// transport specific identifier.
+#[derive(Debug, Clone)]
+pub struct SsrcRegistry<A> {
+	sources: BTreeMap<u32, A>,
+}
+
+impl<A: Clone + PartialEq> SsrcRegistry<A> {
+	/// Constructs an empty registry.
+	pub fn new() -> Self {
+		SsrcRegistry { sources: BTreeMap::new() }
+	}
+
+	/// Observes a packet with the SSRC from the source address.
+	///
+	/// The first source seen for an SSRC is kept, as RFC-3550 requires
+	/// packets from the other source to be discarded.
+	pub fn observe(&mut self, ssrc: u32, src: A) -> SsrcEvent<A> {
+		match self.sources.get(&ssrc) {
+			Some(existing) if *existing == src => return SsrcEvent::Known,
+			Some(existing) => return SsrcEvent::Collision(existing.clone()),
+			None => {}
+		}
+
+		self.sources.insert(ssrc, src);
+		SsrcEvent::New
+	}
+
+	/// Returns the source registered for the SSRC.
+	pub fn source(&self, ssrc: u32) -> Option<&A> {
+		self.sources.get(&ssrc)
+	}
+
+	/// Removes the SSRC, for example after an RTCP BYE or a timeout.
+	pub fn remove(&mut self, ssrc: u32) -> Option<A> {
+		self.sources.remove(&ssrc)
+	}
+
+	/// Returns the number of registered SSRCs.
+	pub fn len(&self) -> usize {
+		self.sources.len()
+	}
+
+	/// Returns true if no SSRCs are registered.
+	pub fn is_empty(&self) -> bool {
+		self.sources.is_empty()
+	}
+}
+
+impl<A: Clone + PartialEq> Default for SsrcRegistry<A> {
+	fn default() -> Self {
+		SsrcRegistry::new()
+	}
+}
+
+/// The probation state of a single source.
+#[derive(Debug, Clone, Copy)]
+struct Probation {
+	max_seq: u16,
+	/// The number of further in-sequence packets required, zero once the
+	/// source is valid.
+	remaining: u16,
+}
+
+/// Holds new SSRCs on probation until they send a run of consecutive
+/// sequence numbers, per RFC-3550 appendix A.1.
+///
+/// This keeps a receiver from acting on spurious sources, such as a
+/// single misdirected or corrupt packet. A gap in the sequence restarts
+/// the run. Once valid a source stays valid until it is removed.
+#[derive(Debug, Clone)]
+pub struct SourceValidator {
+	min_sequential: u16,
+	sources: BTreeMap<u32, Probation>,
+}
+
+impl SourceValidator {
+	/// Constructs a validator requiring `MIN_SEQUENTIAL` consecutive
+	/// packets.
+	pub fn new() -> Self {
+		SourceValidator::with_min_sequential(MIN_SEQUENTIAL)
+	}
+
+	/// Constructs a validator requiring `min_sequential` consecutive
+	/// packets. Values below one are treated as one.
+	pub fn with_min_sequential(min_sequential: u16) -> Self {
+		SourceValidator {
+			min_sequential: min_sequential.max(1),
+			sources: BTreeMap::new(),
+		}
+	}
+
+	/// Observes a packet from the SSRC with the sequence number, returning
+	/// true if the source is valid.
+	pub fn observe(&mut self, ssrc: u32, seq: u16) -> bool {
+		let min_sequential = self.min_sequential;
+		let probation = self.sources.entry(ssrc).or_insert(Probation {
+			max_seq: seq.wrapping_sub(1),
+			remaining: min_sequential,
+		});
+
+		if probation.remaining > 0 {
+			if seq == probation.max_seq.wrapping_add(1) {
+				probation.remaining -= 1;
+			} else {
+				// This packet starts a new run.
+				probation.remaining = min_sequential - 1;
+			}
+		}
+		probation.max_seq = seq;
+
+		probation.remaining == 0
+	}
+
+	/// Returns true if the SSRC has completed probation.
+	pub fn is_valid(&self, ssrc: u32) -> bool {
+		self.sources.get(&ssrc).is_some_and(|p| p.remaining == 0)
+	}
+
+	/// Forgets the SSRC, for example after an RTCP BYE or a timeout.
+	pub fn remove(&mut self, ssrc: u32) {
+		self.sources.remove(&ssrc);
+	}
+}
+
+impl Default for SourceValidator {
+	fn default() -> Self {
+		SourceValidator::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn detects_collision() {
+		let mut registry = SsrcRegistry::new();
+
+		assert_eq!(registry.observe(1, "a"), SsrcEvent::New);
+		assert_eq!(registry.observe(1, "a"), SsrcEvent::Known);
+		assert_eq!(registry.observe(2, "b"), SsrcEvent::New);
+		assert_eq!(registry.observe(1, "b"), SsrcEvent::Collision("a"));
+
+		assert_eq!(registry.source(1), Some(&"a"));
+		assert_eq!(registry.len(), 2);
+	}
+
+	#[test]
+	fn probation_requires_consecutive_packets() {
+		let mut validator = SourceValidator::with_min_sequential(3);
+
+		assert!(!validator.is_valid(1));
+		assert!(!validator.observe(1, 10));
+		assert!(!validator.observe(1, 11));
+		// The gap restarts the run at 20.
+		assert!(!validator.observe(1, 20));
+		assert!(!validator.observe(1, 21));
+		assert!(validator.observe(1, 22));
+		assert!(validator.is_valid(1));
+
+		// Valid sources stay valid across gaps.
+		assert!(validator.observe(1, 40));
+		assert!(!validator.is_valid(2));
+	}
+
+	#[test]
+	fn probation_across_wrap() {
+		let mut validator = SourceValidator::new();
+
+		assert!(!validator.observe(1, 65535));
+		assert!(validator.observe(1, 0));
+
+		validator.remove(1);
+		assert!(!validator.is_valid(1));
+	}
+
+	#[test]
+	fn remove_allows_new_source() {
+		let mut registry = SsrcRegistry::new();
+
+		registry.observe(1, 10u32);
+		assert_eq!(registry.remove(1), Some(10));
+		assert!(registry.is_empty());
+		assert_eq!(registry.observe(1, 20), SsrcEvent::New);
+	}
+}
@@ -0,0 +1,104 @@
+//! The SRTP framing module.
+//!
+//! [RFC-3711](https://tools.ietf.org/html/rfc3711) SRTP packets carry the
+//! RTP header in the clear, followed by the encrypted payload and an
+//! authentication tag. This module locates those parts so that callers can
+//! apply their own cryptography; it does not decrypt or authenticate.
+
+use super::RtpError;
+use super::header::HeaderView;
+
+/// The parts of an SRTP packet.
+#[derive(Debug, Clone, Copy)]
+pub struct SrtpPacket<'a> {
+	header: HeaderView<'a>,
+	authenticated: &'a [u8],
+	payload: &'a [u8],
+	tag: &'a [u8],
+}
+
+impl<'a> SrtpPacket<'a> {
+	/// Splits an SRTP packet with an authentication tag of `tag_len` bytes.
+	///
+	/// If the session uses an MKI, which sits between the payload and the
+	/// tag, include its length in `tag_len`.
+	///
+	/// # Errors
+	///
+	/// If the header does not fit the format per [RFC-1889](https://tools.ietf.org/html/rfc1889)
+	/// or the buffer is too short for the header and tag this method will
+	/// return an Error.
+	pub fn from_buf(buf: &'a [u8], tag_len: usize) -> Result<Self, RtpError> {
+		let header = HeaderView::from_buf(buf)?;
+		let header_len = header.byte_len();
+
+		if buf.len() < header_len + tag_len {
+			return Err(RtpError::BufferTooSmall { needed: header_len + tag_len, got: buf.len() });
+		}
+
+		let (authenticated, tag) = buf.split_at(buf.len() - tag_len);
+
+		Ok(SrtpPacket {
+			header,
+			authenticated,
+			payload: &authenticated[header_len..],
+			tag,
+		})
+	}
+
+	/// Returns the clear text header.
+	pub fn header(&self) -> HeaderView<'a> {
+		self.header
+	}
+
+	/// Returns the authenticated portion of the packet: the header and the
+	/// encrypted payload.
+	pub fn authenticated(&self) -> &'a [u8] {
+		self.authenticated
+	}
+
+	/// Returns the encrypted payload, including any padding.
+	pub fn payload(&self) -> &'a [u8] {
+		self.payload
+	}
+
+	/// Returns the authentication tag (and MKI, if any).
+	pub fn tag(&self) -> &'a [u8] {
+		self.tag
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn split_payload_and_tag() {
+		let buf : &[u8] = &[
+			0b10000001, 96, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x02,
+			0xaa, 0xbb, 0xcc,
+			0x01, 0x02, 0x03, 0x04,
+		];
+
+		let packet = SrtpPacket::from_buf(buf, 4).unwrap();
+
+		assert_eq!(packet.header().sequence(), 1);
+		assert_eq!(packet.authenticated(), &buf[..19]);
+		assert_eq!(packet.payload(), &[0xaa, 0xbb, 0xcc]);
+		assert_eq!(packet.tag(), &[0x01, 0x02, 0x03, 0x04]);
+	}
+
+	#[test]
+	fn too_short_for_tag() {
+		let buf : &[u8] = &[0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0xbb];
+
+		assert!(SrtpPacket::from_buf(buf, 2).unwrap().payload().is_empty());
+		assert_eq!(
+			SrtpPacket::from_buf(buf, 10).unwrap_err(),
+			RtpError::BufferTooSmall { needed: 22, got: 14 }
+		);
+	}
+}
@@ -0,0 +1,241 @@
+//! The RTP reception statistics module.
+//!
+//! These helpers accumulate the statistics a receiver needs to report on a
+//! source per [RFC-3550](https://tools.ietf.org/html/rfc3550), from the
+//! headers of the packets it receives.
+
+use super::sequence::seq_diff;
+
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// Tracks packet loss and reordering from the sequence numbers of a stream.
+///
+/// A gap in the sequence numbers is counted as lost, and a packet arriving
+/// late to fill a gap is counted as reordered and removed from the lost
+/// count. The tracker remembers which of the last 64 sequence numbers up to
+/// the highest were received, so a packet seen twice in that window is
+/// counted as a duplicate and leaves the lost count alone. Packets older
+/// than the window are counted as received only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LossTracker {
+	highest: Option<u16>,
+	// Bit `k` is set when `highest - k` has been received.
+	received_mask: u64,
+	received: u64,
+	lost: u64,
+	reordered: u64,
+	duplicates: u64,
+}
+
+impl LossTracker {
+	/// Constructs a tracker which has not yet seen a packet.
+	pub fn new() -> Self {
+		LossTracker::default()
+	}
+
+	/// Observes the sequence number of the next received packet.
+	pub fn observe(&mut self, seq: u16) {
+		self.received += 1;
+
+		let highest = match self.highest {
+			Some(highest) => highest,
+			None => {
+				self.highest = Some(seq);
+				self.received_mask = 1;
+				return;
+			}
+		};
+
+		let diff = seq_diff(seq, highest);
+		if diff > 0 {
+			self.lost += diff as u64 - 1;
+			self.highest = Some(seq);
+			self.received_mask = self.received_mask.checked_shl(diff as u32).unwrap_or(0) | 1;
+		} else {
+			let bit = match 1u64.checked_shl(-diff as u32) {
+				Some(bit) => bit,
+				None => return,
+			};
+			if self.received_mask & bit != 0 {
+				self.duplicates += 1;
+			} else {
+				self.received_mask |= bit;
+				self.reordered += 1;
+				self.lost = self.lost.saturating_sub(1);
+			}
+		}
+	}
+
+	/// Returns the highest sequence number seen.
+	pub fn highest(&self) -> Option<u16> {
+		self.highest
+	}
+
+	/// Returns the number of packets missing from the sequence.
+	pub fn lost(&self) -> u64 {
+		self.lost
+	}
+
+	/// Returns the number of packets observed.
+	pub fn received(&self) -> u64 {
+		self.received
+	}
+
+	/// Returns the number of packets which arrived out of order.
+	pub fn reordered(&self) -> u64 {
+		self.reordered
+	}
+
+	/// Returns the number of packets which were received more than once.
+	pub fn duplicates(&self) -> u64 {
+		self.duplicates
+	}
+}
+
+/// Estimates the interarrival jitter of a stream.
+///
+/// This implements the estimator of section 6.4.1 of RFC-3550, where the
+/// jitter is a running average of the difference in transit time between
+/// consecutive packets, smoothed with a gain of 1/16.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JitterEstimator {
+	last: Option<(u32, Instant)>,
+	jitter: f64,
+}
+
+#[cfg(feature = "std")]
+impl JitterEstimator {
+	/// Constructs an estimator which has not yet seen a packet.
+	pub fn new() -> Self {
+		JitterEstimator::default()
+	}
+
+	/// Updates the estimate with the RTP timestamp and arrival time of the
+	/// next received packet.
+	///
+	/// The clock rate is used to convert the arrival time into RTP
+	/// timestamp units.
+	pub fn update(&mut self, rtp_timestamp: u32, arrival: Instant, clock_rate: u32) {
+		if let Some((last_timestamp, last_arrival)) = self.last {
+			let arrival_diff = match arrival.checked_duration_since(last_arrival) {
+				Some(elapsed) => elapsed.as_secs_f64(),
+				None => -last_arrival.duration_since(arrival).as_secs_f64(),
+			} * clock_rate as f64;
+			let timestamp_diff = rtp_timestamp.wrapping_sub(last_timestamp) as i32 as f64;
+
+			let d = (arrival_diff - timestamp_diff).abs();
+			self.jitter += (d - self.jitter) / 16.0;
+		}
+
+		self.last = Some((rtp_timestamp, arrival));
+	}
+
+	/// Returns the jitter estimate in RTP timestamp units.
+	pub fn jitter(&self) -> f64 {
+		self.jitter
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn in_order() {
+		let mut tracker = LossTracker::new();
+		for seq in 0..10 {
+			tracker.observe(seq);
+		}
+
+		assert_eq!(tracker.received(), 10);
+		assert_eq!(tracker.lost(), 0);
+		assert_eq!(tracker.reordered(), 0);
+		assert_eq!(tracker.highest(), Some(9));
+	}
+
+	#[test]
+	fn gap_across_wrap() {
+		let mut tracker = LossTracker::new();
+		tracker.observe(65534);
+		tracker.observe(2);
+
+		assert_eq!(tracker.lost(), 3);
+		assert_eq!(tracker.highest(), Some(2));
+	}
+
+	#[test]
+	fn reordered_fills_gap() {
+		let mut tracker = LossTracker::new();
+		tracker.observe(1);
+		tracker.observe(3);
+		assert_eq!(tracker.lost(), 1);
+
+		tracker.observe(2);
+		assert_eq!(tracker.lost(), 0);
+		assert_eq!(tracker.reordered(), 1);
+		assert_eq!(tracker.received(), 3);
+		assert_eq!(tracker.highest(), Some(3));
+	}
+
+	#[test]
+	fn duplicate_does_not_reduce_loss() {
+		let mut tracker = LossTracker::new();
+		tracker.observe(1);
+		tracker.observe(5);
+		assert_eq!(tracker.lost(), 3);
+
+		tracker.observe(3);
+		assert_eq!(tracker.lost(), 2);
+		tracker.observe(3);
+		assert_eq!(tracker.lost(), 2);
+		tracker.observe(5);
+		assert_eq!(tracker.lost(), 2);
+
+		assert_eq!(tracker.reordered(), 1);
+		assert_eq!(tracker.duplicates(), 2);
+		assert_eq!(tracker.received(), 5);
+	}
+
+	#[test]
+	fn packet_older_than_window_keeps_loss() {
+		let mut tracker = LossTracker::new();
+		tracker.observe(0);
+		tracker.observe(100);
+		assert_eq!(tracker.lost(), 99);
+
+		tracker.observe(10);
+		assert_eq!(tracker.lost(), 99);
+		assert_eq!(tracker.reordered(), 0);
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn constant_transit_has_no_jitter() {
+		use std::time::Duration;
+
+		let start = Instant::now();
+		let mut estimator = JitterEstimator::new();
+		for i in 0..10u32 {
+			let ts = 0xffff_ff00u32.wrapping_add(i * 160);
+			estimator.update(ts, start + Duration::from_millis(20 * i as u64), 8000);
+		}
+
+		assert!(estimator.jitter() < 1e-6);
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn late_packet_adds_jitter() {
+		use std::time::Duration;
+
+		let start = Instant::now();
+		let mut estimator = JitterEstimator::new();
+		estimator.update(0, start, 8000);
+		// Arrives 10ms (80 timestamp units) later than expected.
+		estimator.update(160, start + Duration::from_millis(30), 8000);
+
+		assert!((estimator.jitter() - 5.0).abs() < 1e-6);
+	}
+}
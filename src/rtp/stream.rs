@@ -0,0 +1,232 @@
+//! The RTP stream module.
+//!
+//! This module provides iteration over multiple RTP packets packed back to
+//! back in a single buffer, such as TCP-framed RTP or recorded RTP dumps.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use super::RtpError;
+use super::header::Header;
+use super::packet::Packet;
+
+/// The framing used to delimit packets within a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+	/// Each packet is preceded by a two byte Big-Endian length per
+	/// [RFC-4571](https://tools.ietf.org/html/rfc4571).
+	Rfc4571,
+	/// Each packet occupies a record of exactly this many bytes. Any bytes
+	/// in the record after the packet are treated as payload.
+	FixedSize(usize),
+}
+
+/// An iterator over the framed records in a buffer.
+///
+/// If a frame cannot be split from the buffer an error is yielded and
+/// iteration ends.
+#[derive(Debug)]
+struct Frames<'a> {
+	buf: &'a [u8],
+	framing: Framing,
+}
+
+impl<'a> Frames<'a> {
+	/// Splits the next frame from the buffer.
+	fn next_frame(&mut self) -> Result<&'a [u8], RtpError> {
+		match self.framing {
+			Framing::Rfc4571 => {
+				if self.buf.len() < 2 {
+					return Err(RtpError::BufferTooSmall { needed: 2, got: self.buf.len() });
+				}
+
+				let len = NetworkEndian::read_u16(self.buf) as usize;
+				let rest = &self.buf[2..];
+				if rest.len() < len {
+					return Err(RtpError::BufferTooSmall { needed: len, got: rest.len() });
+				}
+
+				let (frame, rest) = rest.split_at(len);
+				self.buf = rest;
+				Ok(frame)
+			}
+			Framing::FixedSize(0) => Err(RtpError::HeaderError("Fixed frame size must be non-zero.")),
+			Framing::FixedSize(size) => {
+				if self.buf.len() < size {
+					return Err(RtpError::BufferTooSmall { needed: size, got: self.buf.len() });
+				}
+
+				let (frame, rest) = self.buf.split_at(size);
+				self.buf = rest;
+				Ok(frame)
+			}
+		}
+	}
+}
+
+impl<'a> Iterator for Frames<'a> {
+	type Item = Result<&'a [u8], RtpError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.buf.is_empty() {
+			return None;
+		}
+
+		match self.next_frame() {
+			Ok(frame) => Some(Ok(frame)),
+			Err(e) => {
+				// The remainder of the buffer cannot be framed.
+				self.buf = &[];
+				Some(Err(e))
+			}
+		}
+	}
+}
+
+/// An iterator over the headers of the framed packets in a buffer.
+///
+/// The iterator stops once the buffer is exhausted. If the final frame is
+/// truncated an error is yielded and iteration ends.
+#[derive(Debug)]
+pub struct PacketIter<'a> {
+	frames: Frames<'a>,
+}
+
+impl<'a> PacketIter<'a> {
+	/// Constructs the iterator over a buffer with the given framing.
+	pub fn new(buf: &'a [u8], framing: Framing) -> Self {
+		PacketIter {
+			frames: Frames { buf, framing },
+		}
+	}
+}
+
+impl<'a> Iterator for PacketIter<'a> {
+	type Item = Result<Header, RtpError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.frames.next().map(|frame| frame.and_then(Header::from_buf))
+	}
+}
+
+/// Parses the framed packets in a buffer, such as a recorded RTP dump,
+/// into headers and payloads.
+///
+/// Framing errors end the iteration as for `PacketIter`. A frame which is
+/// not a valid packet yields an error and iteration continues.
+pub fn parse_stream<'a>(buf: &'a [u8], framing: Framing) -> impl Iterator<Item = Result<Packet, RtpError>> + 'a {
+	Frames { buf, framing }.map(|frame| frame.and_then(Packet::from_buf))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[cfg(not(feature = "std"))]
+	use alloc::vec::Vec;
+
+	#[test]
+	fn iterate_frames() {
+		let buf : &[u8] = &[
+			0x00, 0x0c,
+			0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1,
+			0x00, 0x0d,
+			0b10000000, 96, 0, 2, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa,
+		];
+
+		let headers : Vec<_> = PacketIter::new(buf, Framing::Rfc4571).collect();
+
+		assert_eq!(headers.len(), 2);
+		assert_eq!(headers[0].as_ref().unwrap().sequence(), 1);
+		assert_eq!(headers[1].as_ref().unwrap().sequence(), 2);
+	}
+
+	#[test]
+	fn empty_buffer() {
+		assert_eq!(PacketIter::new(&[], Framing::Rfc4571).count(), 0);
+	}
+
+	#[test]
+	fn truncated_final_frame() {
+		let buf : &[u8] = &[
+			0x00, 0x0c,
+			0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1,
+			0x00, 0x0c,
+			0b10000000, 96, 0, 2,
+		];
+
+		let mut iter = PacketIter::new(buf, Framing::Rfc4571);
+
+		assert!(iter.next().unwrap().is_ok());
+		assert_eq!(iter.next().unwrap(), Err(RtpError::BufferTooSmall { needed: 12, got: 4 }));
+		assert!(iter.next().is_none());
+	}
+
+	#[test]
+	fn truncated_length_prefix() {
+		let mut iter = PacketIter::new(&[0x00], Framing::Rfc4571);
+
+		assert!(iter.next().unwrap().is_err());
+		assert!(iter.next().is_none());
+	}
+
+	#[test]
+	fn fixed_size_frames() {
+		let buf : &[u8] = &[
+			0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa, 0xbb,
+			0b10000000, 96, 0, 2, 0, 0, 0, 1, 0, 0, 0, 1, 0xcc, 0xdd,
+			0b10000000,
+		];
+
+		let mut iter = parse_stream(buf, Framing::FixedSize(14));
+
+		let packet = iter.next().unwrap().unwrap();
+		assert_eq!(packet.header().sequence(), 1);
+		assert_eq!(packet.payload(), &[0xaa, 0xbb]);
+
+		let packet = iter.next().unwrap().unwrap();
+		assert_eq!(packet.header().sequence(), 2);
+		assert_eq!(packet.payload(), &[0xcc, 0xdd]);
+
+		assert_eq!(iter.next().unwrap().unwrap_err(), RtpError::BufferTooSmall { needed: 14, got: 1 });
+		assert!(iter.next().is_none());
+	}
+
+	#[test]
+	fn zero_fixed_size() {
+		let mut iter = PacketIter::new(&[0x80], Framing::FixedSize(0));
+
+		assert!(iter.next().unwrap().is_err());
+		assert!(iter.next().is_none());
+	}
+
+	#[test]
+	fn parse_stream_payloads() {
+		let buf : &[u8] = &[
+			0x00, 0x0d,
+			0b10000000, 96, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0xaa,
+			0x00, 0x02, 0xaa, 0xbb,
+			0x00, 0x0c,
+			0b10000000, 96, 0, 3, 0, 0, 0, 1, 0, 0, 0, 1,
+		];
+
+		let packets : Vec<_> = parse_stream(buf, Framing::Rfc4571).collect();
+
+		assert_eq!(packets.len(), 3);
+		assert_eq!(packets[0].as_ref().unwrap().payload(), &[0xaa]);
+		assert!(packets[1].is_err());
+		assert_eq!(packets[2].as_ref().unwrap().header().sequence(), 3);
+	}
+
+	#[test]
+	fn invalid_frame_continues() {
+		let buf : &[u8] = &[
+			0x00, 0x02, 0xaa, 0xbb,
+			0x00, 0x0c,
+			0b10000000, 96, 0, 2, 0, 0, 0, 1, 0, 0, 0, 1,
+		];
+
+		let mut iter = PacketIter::new(buf, Framing::Rfc4571);
+
+		assert!(iter.next().unwrap().is_err());
+		assert_eq!(iter.next().unwrap().unwrap().sequence(), 2);
+	}
+}
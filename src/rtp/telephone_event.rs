@@ -0,0 +1,96 @@
+//! The RTP telephone-event module.
+//!
+//! This module parses the telephone-event payload used to carry DTMF
+//! digits and other telephony events per [RFC-4733](https://tools.ietf.org/html/rfc4733#section-2.3).
+//!
+//!  0                   1                   2                   3
+//!  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |     event     |E|R| volume    |          duration             |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+use byteorder::{ByteOrder, NetworkEndian};
+use super::RtpError;
+
+/// A telephone-event payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelephoneEvent {
+	event: u8,
+	end: bool,
+	volume: u8,
+	duration: u16,
+}
+
+impl TelephoneEvent {
+	/// Construct the event from the first 4 bytes of a payload.
+	///
+	/// # Errors
+	///
+	/// If the payload is smaller than 4 bytes this method will return an
+	/// Error.
+	pub fn from_buf(payload: &[u8]) -> Result<Self, RtpError> {
+		if payload.len() < 4 {
+			return Err(RtpError::BufferTooSmall { needed: 4, got: payload.len() });
+		}
+
+		Ok(TelephoneEvent {
+			event: payload[0],
+			end: payload[1] & 0x80 != 0,
+			volume: payload[1] & 0x3f,
+			duration: NetworkEndian::read_u16(&payload[2..]),
+		})
+	}
+
+	/// Return the event code. The DTMF digits 0-9 are events 0-9, `*` is
+	/// 10, `#` is 11 and A-D are 12-15.
+	pub fn event(&self) -> u8 {
+		self.event
+	}
+
+	/// Returns true if this is the final packet of the event.
+	pub fn is_end(&self) -> bool {
+		self.end
+	}
+
+	/// Return the volume as a power level in -dBm0, from 0 to 63.
+	pub fn volume(&self) -> u8 {
+		self.volume
+	}
+
+	/// Return the duration of the event so far in timestamp units.
+	pub fn duration(&self) -> u16 {
+		self.duration
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dtmf_digit() {
+		// Digit 5, end of event, volume 10, duration 800.
+		let payload : &[u8] = &[0x05, 0b10001010, 0x03, 0x20];
+
+		let event = TelephoneEvent::from_buf(payload).unwrap();
+
+		assert_eq!(event.event(), 5);
+		assert!(event.is_end());
+		assert_eq!(event.volume(), 10);
+		assert_eq!(event.duration(), 800);
+	}
+
+	#[test]
+	fn reserved_bit_is_ignored() {
+		let event = TelephoneEvent::from_buf(&[11, 0b01111111, 0, 160]).unwrap();
+
+		assert_eq!(event.event(), 11);
+		assert!(!event.is_end());
+		assert_eq!(event.volume(), 63);
+	}
+
+	#[test]
+	fn short_payload() {
+		assert_eq!(TelephoneEvent::from_buf(&[5, 0, 0]), Err(RtpError::BufferTooSmall { needed: 4, got: 3 }));
+	}
+}
@@ -0,0 +1,248 @@
+//! The RTP timestamp module.
+//!
+//! This module provides a timestamp type with wrapping arithmetic and the
+//! generation of timestamps for outgoing streams per
+//! [RFC-3550](https://tools.ietf.org/html/rfc3550#section-5.1).
+
+use core::ops::{Add, Sub};
+use super::random;
+
+/// An RTP timestamp.
+///
+/// RTP timestamps are 32 bits and wrap, so arithmetic on them wraps rather
+/// than overflowing. Whether one timestamp is newer than another is decided
+/// with serial number arithmetic per [RFC-1982](https://tools.ietf.org/html/rfc1982),
+/// treating the larger raw value as newer at the half-way distance of 2^31.
+///
+/// Three timestamps spread over more than half of the 32 bit space have no
+/// consistent order, so there are no comparison operators. Timestamps which
+/// must be sorted are first extended with a `TimestampExtender`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RtpTimestamp(pub u32);
+
+impl RtpTimestamp {
+	/// Returns the timestamp advanced by a number of samples.
+	pub fn advance(self, samples: u32) -> Self {
+		self + samples
+	}
+
+	/// Returns the signed number of ticks from `other` to this timestamp,
+	/// positive when this timestamp is newer.
+	pub fn diff(self, other: RtpTimestamp) -> i64 {
+		let diff = self.0.wrapping_sub(other.0);
+
+		if diff == 0x8000_0000 {
+			if self.0 > other.0 { 0x8000_0000 } else { -0x8000_0000 }
+		} else {
+			diff as i32 as i64
+		}
+	}
+
+	/// Returns true if this timestamp is newer than `other`, that is if
+	/// `diff` is positive.
+	pub fn is_newer_than(self, other: RtpTimestamp) -> bool {
+		self.diff(other) > 0
+	}
+}
+
+impl From<u32> for RtpTimestamp {
+	fn from(ts: u32) -> Self {
+		RtpTimestamp(ts)
+	}
+}
+
+impl From<RtpTimestamp> for u32 {
+	fn from(ts: RtpTimestamp) -> Self {
+		ts.0
+	}
+}
+
+impl Add<u32> for RtpTimestamp {
+	type Output = RtpTimestamp;
+
+	fn add(self, ticks: u32) -> RtpTimestamp {
+		RtpTimestamp(self.0.wrapping_add(ticks))
+	}
+}
+
+impl Sub<u32> for RtpTimestamp {
+	type Output = RtpTimestamp;
+
+	fn sub(self, ticks: u32) -> RtpTimestamp {
+		RtpTimestamp(self.0.wrapping_sub(ticks))
+	}
+}
+
+impl Sub for RtpTimestamp {
+	type Output = u32;
+
+	/// Returns the number of ticks from `other` forwards to this
+	/// timestamp, wrapping. Use `diff` for a signed distance.
+	fn sub(self, other: RtpTimestamp) -> u32 {
+		self.0.wrapping_sub(other.0)
+	}
+}
+
+/// An RTP timestamp extended past 32 bits with its wrap count.
+///
+/// Extended timestamps are plain integers, so they are totally ordered and
+/// their differences do not wrap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExtendedTimestamp(pub i64);
+
+impl ExtendedTimestamp {
+	/// Returns the 32 bit RTP timestamp.
+	pub fn timestamp(self) -> RtpTimestamp {
+		RtpTimestamp(self.0 as u32)
+	}
+}
+
+/// Unwraps the RTP timestamps of a stream into `ExtendedTimestamp`s.
+///
+/// Each timestamp is extended relative to the previous one, which is
+/// correct while consecutive timestamps are less than 2^31 ticks apart.
+/// The first timestamp keeps its raw value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimestampExtender {
+	last: Option<ExtendedTimestamp>,
+}
+
+impl TimestampExtender {
+	/// Constructs an extender which has not yet seen a timestamp.
+	pub fn new() -> Self {
+		TimestampExtender::default()
+	}
+
+	/// Returns the extended timestamp of the next packet.
+	pub fn extend(&mut self, ts: u32) -> ExtendedTimestamp {
+		let extended = match self.last {
+			Some(last) => ExtendedTimestamp(last.0 + RtpTimestamp(ts).diff(last.timestamp())),
+			None => ExtendedTimestamp(ts as i64),
+		};
+
+		self.last = Some(extended);
+		extended
+	}
+}
+
+/// Generates the timestamps of an outgoing stream.
+///
+/// The timestamp advances by the number of samples in each packet and
+/// wraps at 32 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampGenerator {
+	clock_rate: u32,
+	next: u32,
+}
+
+impl TimestampGenerator {
+	/// Constructs a generator for the given clock rate, whose first
+	/// timestamp is `random_start`.
+	pub fn new(clock_rate: u32, random_start: u32) -> Self {
+		TimestampGenerator {
+			clock_rate,
+			next: random_start,
+		}
+	}
+
+	/// Constructs a generator starting at a random timestamp, as
+	/// recommended by RFC-3550.
+	#[cfg(feature = "std")]
+	pub fn new_random(clock_rate: u32) -> Self {
+		TimestampGenerator::from_seed(clock_rate, random::seed())
+	}
+
+	/// Constructs a generator starting at a timestamp derived from the
+	/// seed, so that tests are reproducible.
+	pub fn from_seed(clock_rate: u32, seed: u64) -> Self {
+		let mut state = seed;
+		TimestampGenerator::new(clock_rate, random::splitmix64(&mut state) as u32)
+	}
+
+	/// Returns the timestamp for the next packet and advances by the
+	/// number of samples it contains.
+	pub fn advance(&mut self, samples: u32) -> u32 {
+		let ts = self.next;
+		self.next = ts.wrapping_add(samples);
+		ts
+	}
+
+	/// Returns the timestamp `advance` will return, without advancing.
+	pub fn peek(&self) -> u32 {
+		self.next
+	}
+
+	/// Return the clock rate of the stream.
+	pub fn clock_rate(&self) -> u32 {
+		self.clock_rate
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn timestamp_arithmetic_wraps() {
+		let ts = RtpTimestamp(u32::MAX - 1);
+
+		assert_eq!(ts.advance(3), RtpTimestamp(1));
+		assert_eq!(ts + 2, RtpTimestamp(0));
+		assert_eq!(RtpTimestamp(1) - 3, ts);
+		assert_eq!(RtpTimestamp(1) - ts, 3);
+		assert_eq!(ts - RtpTimestamp(1), u32::MAX - 2);
+		assert_eq!(u32::from(RtpTimestamp::from(7)), 7);
+	}
+
+	#[test]
+	fn timestamp_ordering_across_wrap() {
+		let before = RtpTimestamp(u32::MAX - 10);
+		let after = RtpTimestamp(10);
+
+		assert!(after.is_newer_than(before));
+		assert!(!before.is_newer_than(after));
+		assert_eq!(after.diff(before), 21);
+		assert_eq!(before.diff(after), -21);
+
+		let half = RtpTimestamp(0x8000_0000);
+		assert!(half.is_newer_than(RtpTimestamp(0)));
+		assert!(!RtpTimestamp(0).is_newer_than(half));
+	}
+
+	#[test]
+	fn extender_orders_across_wrap() {
+		let mut extender = TimestampExtender::new();
+
+		let before = extender.extend(u32::MAX - 10);
+		let after = extender.extend(10);
+		let late = extender.extend(u32::MAX);
+
+		assert!(before < late && late < after);
+		assert_eq!(after, ExtendedTimestamp(u32::MAX as i64 + 11));
+		assert_eq!(after.0 - before.0, 21);
+		assert_eq!(after.timestamp(), RtpTimestamp(10));
+	}
+
+	#[test]
+	fn first_packet_uses_start() {
+		let mut gen = TimestampGenerator::new(8000, 1234);
+
+		assert_eq!(gen.advance(160), 1234);
+		assert_eq!(gen.advance(160), 1394);
+		assert_eq!(gen.peek(), 1554);
+		assert_eq!(gen.clock_rate(), 8000);
+	}
+
+	#[test]
+	fn advance_wraps() {
+		let mut gen = TimestampGenerator::new(48000, u32::MAX - 100);
+
+		assert_eq!(gen.advance(960), u32::MAX - 100);
+		assert_eq!(gen.advance(960), 859);
+	}
+
+	#[test]
+	fn seed_is_reproducible() {
+		assert_eq!(TimestampGenerator::from_seed(8000, 7), TimestampGenerator::from_seed(8000, 7));
+	}
+}